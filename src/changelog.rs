@@ -0,0 +1,350 @@
+use crate::cli::{git_output, select_template};
+use crate::config::{Arguments, GlitterRc};
+use crate::lint::{parse, range_messages};
+use colored::*;
+use inflector::Inflector;
+use serde::Serialize;
+use std::io::Error;
+
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct Entry {
+	pub sha: String,
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub scope: Option<String>,
+	pub description: String,
+	pub breaking: bool,
+}
+
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct Section {
+	pub title: String,
+	pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct Changelog {
+	/// the tag the changes were released as, or `Unreleased`
+	pub version: String,
+	pub date: Option<String>,
+	pub sections: Vec<Section>,
+}
+
+// Keep a Changelog titles for the usual types, anything else is titled after itself
+fn default_title(kind: &str) -> String {
+	match kind {
+		"feat" => "Added".to_owned(),
+		"fix" => "Fixed".to_owned(),
+		"perf" | "refactor" => "Changed".to_owned(),
+		"deprecate" => "Deprecated".to_owned(),
+		"remove" => "Removed".to_owned(),
+		"security" => "Security".to_owned(),
+		kind => kind.to_sentence_case(),
+	}
+}
+
+fn is_breaking(subject: &str, message: &str) -> bool {
+	subject
+		.split_once(": ")
+		.is_some_and(|(prefix, _)| prefix.ends_with('!'))
+		|| message.lines().any(|line| {
+			line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+		})
+}
+
+// read `(sha, message)` pairs back into entries, subjects that don't match the template are left out
+pub fn entries(config: &GlitterRc, messages: &[(String, String)]) -> anyhow::Result<Vec<Entry>> {
+	let mut entries: Vec<Entry> = Vec::new();
+	for (sha, message) in messages {
+		let subject = message.trim().lines().next().unwrap_or_default().trim();
+		let (parsed_config, values) = match parse(config, subject)? {
+			Some(parsed) => parsed,
+			None => continue,
+		};
+		let value = |idx: usize| {
+			values
+				.iter()
+				.find(|(i, value)| *i == idx && !value.is_empty())
+				.map(|(_, value)| value.clone())
+		};
+		let kind = match value(1) {
+			Some(kind) => kind,
+			None => continue,
+		};
+		let scope = parsed_config
+			.commit_message_arguments
+			.iter()
+			.flatten()
+			.find(|arg| arg.name.as_deref() == Some("scope"))
+			.and_then(|arg| value(arg.argument as usize));
+		// the last argument is the description, usually a `$N+`
+		let description = values
+			.iter()
+			.map(|(idx, _)| *idx)
+			.max()
+			.filter(|idx| *idx > 1)
+			.and_then(value)
+			.unwrap_or_else(|| subject.to_owned());
+		entries.push(Entry {
+			sha: sha.clone(),
+			kind,
+			scope,
+			description,
+			breaking: is_breaking(subject, message),
+		});
+	}
+
+	Ok(entries)
+}
+
+// group entries into sections by their type, in the order of the type_enums of argument 1
+pub fn group(
+	config: &GlitterRc,
+	version: String,
+	date: Option<String>,
+	entries: &[Entry],
+) -> Changelog {
+	let titles = config
+		.changelog
+		.as_ref()
+		.and_then(|changelog| changelog.sections.clone());
+	let mut kinds = config
+		.commit_message_arguments
+		.iter()
+		.flatten()
+		.find(|arg| arg.argument == 1)
+		.and_then(|arg| arg.type_enums.clone())
+		.unwrap_or_default();
+	for entry in entries {
+		if !kinds.contains(&entry.kind) {
+			kinds.push(entry.kind.clone());
+		}
+	}
+	let mut sections: Vec<Section> = Vec::new();
+	for kind in kinds {
+		let title = match titles {
+			Some(ref titles) => match titles.get(&kind) {
+				Some(title) => title.clone(),
+				None => continue,
+			},
+			None => default_title(&kind),
+		};
+		let kind_entries = entries
+			.iter()
+			.filter(|entry| entry.kind == kind)
+			.cloned()
+			.collect::<Vec<Entry>>();
+		if kind_entries.is_empty() {
+			continue;
+		}
+		// several types can share a section, eg. `perf` and `refactor` under Changed
+		match sections.iter_mut().find(|section| section.title == title) {
+			Some(section) => section.entries.extend(kind_entries),
+			None => sections.push(Section {
+				title,
+				entries: kind_entries,
+			}),
+		}
+	}
+
+	Changelog {
+		version,
+		date,
+		sections,
+	}
+}
+
+// https://keepachangelog.com
+pub fn markdown(changelog: &Changelog) -> String {
+	let mut result = match changelog.date {
+		Some(ref date) => format!("## [{}] - {}\n", changelog.version, date),
+		None => format!("## [{}]\n", changelog.version),
+	};
+	for section in &changelog.sections {
+		result.push_str(&format!("\n### {}\n\n", section.title));
+		for entry in &section.entries {
+			result.push_str(&format!(
+				"- {}{}{} ({})\n",
+				if entry.breaking { "**BREAKING** " } else { "" },
+				entry
+					.scope
+					.as_ref()
+					.map(|scope| format!("**{}:** ", scope))
+					.unwrap_or_default(),
+				entry.description,
+				entry.sha
+			));
+		}
+	}
+	result
+}
+
+// the changes between two refs, `from` defaults to the latest tag (or the whole history without one)
+pub fn collect(config: &GlitterRc, from: Option<&str>, to: &str) -> anyhow::Result<Changelog> {
+	let latest_tag = git_output(&["describe", "--tags", "--abbrev=0", to]);
+	let from = from
+		.map(str::to_owned)
+		.or_else(|| Some(latest_tag).filter(|tag| !tag.is_empty()));
+	let range = match from {
+		Some(ref from) => format!("{}..{}", from, to),
+		None => to.to_owned(),
+	};
+	let mut messages = range_messages(&[&range])?;
+	messages.reverse();
+
+	let (version, date) = if to == "HEAD" {
+		("Unreleased".to_owned(), None)
+	} else {
+		(
+			to.to_owned(),
+			Some(git_output(&["log", "-1", "--format=%cs", to])).filter(|date| !date.is_empty()),
+		)
+	};
+	Ok(group(config, version, date, &entries(config, &messages)?))
+}
+
+// `from to`, `from..to` or just `from`. a side left out, like in `v1.0.0..`, is the default
+fn refs(arguments: &[String]) -> anyhow::Result<(Option<String>, String)> {
+	let (from, to) = match arguments {
+		[] => (None, None),
+		[range] => match range.split_once("..") {
+			Some((from, to)) => (Some(from), Some(to)),
+			None => (Some(range.as_str()), None),
+		},
+		[from, to] => (Some(from.as_str()), Some(to.as_str())),
+		_ => {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"`changelog` takes at most two refs, eg. {}, but got {}",
+					"glitter changelog v1.0.0 v1.1.0".green(),
+					arguments.len()
+				),
+			)))
+		}
+	};
+	Ok((
+		from.filter(|from| !from.is_empty()).map(str::to_owned),
+		to.filter(|to| !to.is_empty()).unwrap_or("HEAD").to_owned(),
+	))
+}
+
+pub fn changelog(config: GlitterRc, args: Arguments) -> anyhow::Result<()> {
+	let config = select_template(config, args.template.as_deref())?;
+	let (from, to) = refs(&args.arguments)?;
+	let changelog = collect(&config, from.as_deref(), &to)?;
+
+	match args.format.as_deref().unwrap_or("markdown") {
+		"markdown" | "md" => print!("{}", markdown(&changelog)),
+		"json" => println!("{}", serde_json::to_string_pretty(&changelog)?),
+		format => {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"Unknown changelog format `{}`. Use `markdown` or `json`.",
+					format
+				),
+			)))
+		}
+	}
+	Ok(())
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use crate::config::{ChangelogOptions, GlitterRc, Preset};
+	use crate::conventional::apply_preset;
+
+	use super::{entries, group, markdown, refs};
+
+	fn config(sections: Option<HashMap<String, String>>) -> GlitterRc {
+		apply_preset(
+			GlitterRc {
+				preset: Some(Preset::Conventional),
+				changelog: Some(ChangelogOptions { sections }),
+				..Default::default()
+			},
+			false,
+		)
+	}
+
+	fn messages() -> Vec<(String, String)> {
+		vec![
+			("a1".to_owned(), "fix(cli): handle empty input".to_owned()),
+			("b2".to_owned(), "Merge branch 'x'".to_owned()),
+			(
+				"c3".to_owned(),
+				"feat!: drop v1\n\nBREAKING CHANGE: v1 is gone".to_owned(),
+			),
+			("d4".to_owned(), "perf: cache the config".to_owned()),
+			("e5".to_owned(), "refactor(lint): split parse".to_owned()),
+		]
+	}
+
+	#[test]
+	fn groups_by_type() {
+		let config = config(None);
+		let changelog = group(
+			&config,
+			"Unreleased".to_owned(),
+			None,
+			&entries(&config, &messages()).unwrap(),
+		);
+		assert_eq!(
+			changelog
+				.sections
+				.iter()
+				.map(|section| (section.title.as_str(), section.entries.len()))
+				.collect::<Vec<_>>(),
+			vec![("Added", 1), ("Fixed", 1), ("Changed", 2)]
+		);
+		assert_eq!(
+			markdown(&changelog),
+			"## [Unreleased]\n\n### Added\n\n- **BREAKING** drop v1 (c3)\n\n### Fixed\n\n- **cli:** handle empty input (a1)\n\n### Changed\n\n- cache the config (d4)\n- **lint:** split parse (e5)\n"
+		);
+	}
+
+	#[test]
+	fn configured_sections() {
+		let mut sections = HashMap::new();
+		sections.insert("fix".to_owned(), "Bug Fixes".to_owned());
+		let config = config(Some(sections));
+		let changelog = group(
+			&config,
+			"v1.2.0".to_owned(),
+			Some("2026-01-02".to_owned()),
+			&entries(&config, &messages()).unwrap(),
+		);
+		assert_eq!(changelog.sections.len(), 1);
+		assert!(markdown(&changelog).starts_with("## [v1.2.0] - 2026-01-02\n\n### Bug Fixes\n"));
+	}
+
+	#[test]
+	fn reads_the_refs() {
+		let refs = |arguments: &[&str]| {
+			refs(
+				&arguments
+					.iter()
+					.map(|arg| arg.to_string())
+					.collect::<Vec<String>>(),
+			)
+		};
+		assert_eq!(refs(&[]).unwrap(), (None, "HEAD".to_owned()));
+		assert_eq!(
+			refs(&["v0.1.0"]).unwrap(),
+			(Some("v0.1.0".to_owned()), "HEAD".to_owned())
+		);
+		assert_eq!(
+			refs(&["v0.1.0.."]).unwrap(),
+			(Some("v0.1.0".to_owned()), "HEAD".to_owned())
+		);
+		assert_eq!(
+			refs(&["v0.1.0..v0.2.0"]).unwrap(),
+			refs(&["v0.1.0", "v0.2.0"]).unwrap()
+		);
+		assert!(refs(&["v0.1.0", "v0.2.0", "v0.3.0"]).is_err());
+	}
+}
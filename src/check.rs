@@ -0,0 +1,741 @@
+use crate::cli::{apply_template, argument_index};
+use crate::config::{Arguments, Case, GlitterRc, Transform};
+use crate::conventional::apply_preset;
+use crate::get_and_parse::{config_dir, detect, extends, layers, merge, parse, resolve, to_value};
+use crate::template::{tokenize, Token};
+use colored::*;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Diagnostic {
+	pub file: PathBuf,
+	/// 1-based line and column, when the problem could be found in the file
+	pub position: Option<(usize, usize)>,
+	pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.position {
+			Some((line, column)) => write!(
+				f,
+				"{}:{}:{}: {}",
+				self.file.display(),
+				line,
+				column,
+				self.message
+			),
+			None => write!(f, "{}: {}", self.file.display(), self.message),
+		}
+	}
+}
+
+fn is_word(c: char) -> bool {
+	c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+// the offset of `needle` as a whole key or value at or after `from`, either quoted (`"fmt"`) or
+// bare like in toml keys and yaml (`fmt:`, `- fmt`, `[fmt]`). `cargo fmt` doesn't count as `fmt`
+pub fn find_token(content: &str, needle: &str, from: usize) -> Option<usize> {
+	content
+		.match_indices(needle)
+		.map(|(i, _)| i)
+		.filter(|i| *i >= from)
+		.find(|&i| {
+			let after = content[i + needle.len()..].chars().next();
+			match content[..i].chars().last() {
+				Some(quote @ ('"' | '\'')) => after == Some(quote),
+				_ => {
+					let line = content[..i].rsplit('\n').next().unwrap_or_default();
+					!after.is_some_and(is_word)
+						&& line
+							.trim_end()
+							.chars()
+							.last()
+							.is_none_or(|c| "{[,:=-".contains(c))
+				}
+			}
+		})
+}
+
+// 1-based line and column of a byte offset, columns count characters
+pub fn position(content: &str, offset: usize) -> (usize, usize) {
+	let before = &content[..offset];
+	(
+		before.matches('\n').count() + 1,
+		before
+			.rsplit('\n')
+			.next()
+			.unwrap_or_default()
+			.chars()
+			.count() + 1,
+	)
+}
+
+// follow a key path like `commit_message_arguments.0.case` through the file, looking for each
+// key after the one before it. list indexes can't be seen in the text so they're skipped
+fn locate_path(content: &str, path: &[String]) -> Option<usize> {
+	let mut offset = None;
+	for key in path.iter().filter(|key| key.parse::<usize>().is_err()) {
+		offset = Some(find_token(content, key, offset.unwrap_or(0))?);
+	}
+	offset
+}
+
+// serde_ignored paths have `?` segments for options, only the keys and indexes are kept
+fn segments(path: &serde_ignored::Path) -> Vec<String> {
+	match path {
+		serde_ignored::Path::Root => Vec::new(),
+		serde_ignored::Path::Seq { parent, index } => {
+			let mut segments = segments(parent);
+			segments.push(index.to_string());
+			segments
+		}
+		serde_ignored::Path::Map { parent, key } => {
+			let mut segments = segments(parent);
+			segments.push(key.clone());
+			segments
+		}
+		serde_ignored::Path::Some { parent }
+		| serde_ignored::Path::NewtypeStruct { parent }
+		| serde_ignored::Path::NewtypeVariant { parent } => segments(parent),
+	}
+}
+
+// every `case` and `word_case` that isn't a case glitter knows, as `(path, value, error)`.
+// they're swapped for a valid case so the rest of the file can still be checked
+fn unknown_cases(
+	value: &mut Value,
+	path: &mut Vec<String>,
+	found: &mut Vec<(Vec<String>, String, String)>,
+) {
+	match value {
+		Value::Object(map) => {
+			for (key, value) in map.iter_mut() {
+				path.push(key.clone());
+				match value {
+					Value::String(case) if key == "case" || key == "word_case" => {
+						if let Err(err) =
+							serde_json::from_value::<Case>(Value::String(case.clone()))
+						{
+							found.push((path.clone(), case.clone(), err.to_string()));
+							*value = Value::String("lower".to_owned());
+						}
+					}
+					value => unknown_cases(value, path, found),
+				}
+				path.pop();
+			}
+		}
+		Value::Array(list) => {
+			for (idx, value) in list.iter_mut().enumerate() {
+				path.push(idx.to_string());
+				unknown_cases(value, path, found);
+				path.pop();
+			}
+		}
+		_ => {}
+	}
+}
+
+// every step of a `transforms` list that isn't a transform glitter knows, as
+// `(path, name, error)`. they're dropped so the rest of the file can still be checked
+fn unknown_transforms(
+	value: &mut Value,
+	path: &mut Vec<String>,
+	found: &mut Vec<(Vec<String>, String, String)>,
+) {
+	match value {
+		Value::Object(map) => {
+			for (key, value) in map.iter_mut() {
+				path.push(key.clone());
+				match value {
+					Value::Array(steps) if key == "transforms" => steps.retain(|step| {
+						let err = match serde_json::from_value::<Transform>(step.clone()) {
+							Ok(_) => return true,
+							Err(err) => err,
+						};
+						// `"trim"` or the key of `{ "truncate": 50 }`
+						let name = match step {
+							Value::String(name) => name.clone(),
+							Value::Object(step) => step.keys().next().cloned().unwrap_or_default(),
+							step => step.to_string(),
+						};
+						found.push((path.clone(), name, err.to_string()));
+						false
+					}),
+					value => unknown_transforms(value, path, found),
+				}
+				path.pop();
+			}
+		}
+		Value::Array(list) => {
+			for (idx, value) in list.iter_mut().enumerate() {
+				path.push(idx.to_string());
+				unknown_transforms(value, path, found);
+				path.pop();
+			}
+		}
+		_ => {}
+	}
+}
+
+// the keys and indexes of a serde_path_to_error path
+fn error_segments(path: &serde_path_to_error::Path) -> Vec<String> {
+	path.iter()
+		.filter_map(|segment| match segment {
+			serde_path_to_error::Segment::Seq { index } => Some(index.to_string()),
+			serde_path_to_error::Segment::Map { key } => Some(key.clone()),
+			serde_path_to_error::Segment::Enum { variant } => Some(variant.clone()),
+			serde_path_to_error::Segment::Unknown => None,
+		})
+		.collect()
+}
+
+// remove the value at `path`, false if there's nothing there (or the path is the whole config)
+fn remove_path(value: &mut Value, path: &[String]) -> bool {
+	let (last, parents) = match path.split_last() {
+		Some(split) => split,
+		None => return false,
+	};
+	let mut parent = value;
+	for key in parents {
+		parent = match parent {
+			Value::Object(map) => match map.get_mut(key) {
+				Some(child) => child,
+				None => return false,
+			},
+			Value::Array(list) => match key.parse::<usize>().ok().and_then(|idx| list.get_mut(idx))
+			{
+				Some(child) => child,
+				None => return false,
+			},
+			_ => return false,
+		};
+	}
+	match parent {
+		Value::Object(map) => map.remove(last).is_some(),
+		Value::Array(list) => match last.parse::<usize>() {
+			Ok(idx) if idx < list.len() => {
+				list.remove(idx);
+				true
+			}
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+// check a single file and the files it extends. they're added to `files` in the order they're
+// loaded, with what could be read of the config so the layers can still be checked together
+pub fn check_file(
+	path: &Path,
+	config_dir: Option<&Path>,
+	files: &mut Vec<(PathBuf, String, Value)>,
+	seen: &mut Vec<PathBuf>,
+	diagnostics: &mut Vec<Diagnostic>,
+) {
+	let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+	if seen.contains(&canonical) {
+		return;
+	}
+	seen.push(canonical);
+	let mut diagnostic = |position: Option<(usize, usize)>, message: String| {
+		diagnostics.push(Diagnostic {
+			file: path.to_path_buf(),
+			position,
+			message,
+		})
+	};
+	let content = match fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(err) => return diagnostic(None, format!("Couldn't read the file: {}", err)),
+	};
+	let format = detect(path, &content);
+	let mut value = match to_value(&content, format) {
+		Ok(value) => value,
+		Err(err) => return diagnostic(None, format!("Not valid {}: {}", format, err)),
+	};
+	let at = |path: &[String]| locate_path(&content, path).map(|offset| position(&content, offset));
+
+	match extends(&mut value, path) {
+		Ok(names) => {
+			for name in names {
+				match resolve(&name, path, config_dir) {
+					Ok(extended) => check_file(&extended, config_dir, files, seen, diagnostics),
+					Err(err) => diagnostics.push(Diagnostic {
+						file: path.to_path_buf(),
+						position: at(&["extends".to_owned(), name]),
+						message: err.to_string(),
+					}),
+				}
+			}
+		}
+		Err(err) => diagnostics.push(Diagnostic {
+			file: path.to_path_buf(),
+			position: at(&["extends".to_owned()]),
+			message: err.to_string(),
+		}),
+	}
+	let mut diagnostic = |position: Option<(usize, usize)>, message: String| {
+		diagnostics.push(Diagnostic {
+			file: path.to_path_buf(),
+			position,
+			message,
+		})
+	};
+	// `hooks+` and friends are checked as the key they append to
+	let mut config = Value::Object(Map::new());
+	if let Err(err) = merge(&mut config, value) {
+		return diagnostic(None, err.to_string());
+	}
+
+	let mut cases = Vec::new();
+	unknown_cases(&mut config, &mut Vec::new(), &mut cases);
+	for (mut path, case, err) in cases {
+		path.push(case);
+		diagnostic(at(&path), format!("Unknown case: {}", err));
+	}
+	let mut transforms = Vec::new();
+	unknown_transforms(&mut config, &mut Vec::new(), &mut transforms);
+	for (mut path, name, err) in transforms {
+		path.push(name);
+		diagnostic(at(&path), format!("Unknown transform: {}", err));
+	}
+
+	// deserializing stops at the first value of the wrong type, so each one is reported where it
+	// is and left out until the rest of the file reads
+	let mut unknown: Vec<Vec<String>> = Vec::new();
+	loop {
+		let mut ignored = Vec::new();
+		let result: Result<GlitterRc, _> = serde_path_to_error::deserialize(
+			serde_ignored::Deserializer::new(config.clone(), &mut |path: serde_ignored::Path| {
+				ignored.push(segments(&path))
+			}),
+		);
+		for path in ignored {
+			if !unknown.contains(&path) {
+				unknown.push(path);
+			}
+		}
+		let err = match result {
+			Ok(_) => break,
+			Err(err) => err,
+		};
+		let path = error_segments(err.path());
+		if path.is_empty() {
+			diagnostic(None, err.inner().to_string());
+			break;
+		}
+		diagnostic(
+			at(&path),
+			format!("Invalid `{}`: {}", path.join("."), err.inner()),
+		);
+		if !remove_path(&mut config, &path) {
+			break;
+		}
+	}
+	for path in unknown {
+		diagnostic(at(&path), format!("Unknown key `{}`", path.join(".")));
+	}
+	files.push((path.to_path_buf(), content.clone(), config));
+}
+
+// the highest argument position a template uses, named placeholders included
+fn highest_index(config: &GlitterRc, tokens: &[Token]) -> usize {
+	tokens
+		.iter()
+		.map(|token| match token {
+			Token::Argument { key, .. } => argument_index(config, key).unwrap_or(0),
+			Token::Group(inner) => highest_index(config, inner),
+			Token::Literal(_) => 0,
+		})
+		.max()
+		.unwrap_or(0)
+}
+
+// where a problem is in the files: a value, and the key it's set with when the value alone is
+// ambiguous, eg. a task name that's also in `hooks`
+pub type Needle = (Option<&'static str>, String);
+
+// problems with the config as a whole, each with what to point at in the files
+pub fn problems(config: &GlitterRc) -> Vec<(String, Option<Needle>)> {
+	let mut problems: Vec<(String, Option<Needle>)> = Vec::new();
+	let tasks = config.custom_tasks.clone().unwrap_or_default();
+	let names = tasks
+		.iter()
+		.map(|task| task.name.clone())
+		.collect::<Vec<String>>();
+
+	for (idx, name) in names.iter().enumerate() {
+		let count = names.iter().filter(|other| *other == name).count();
+		if count > 1 && !names[..idx].contains(name) {
+			problems.push((
+				format!("The task `{}` is defined {} times", name, count),
+				Some((Some("name"), name.clone())),
+			));
+		}
+	}
+
+	let release_tasks = config
+		.release
+		.as_ref()
+		.and_then(|release| release.tasks.clone());
+	for (key, hooks) in [
+		("hooks", config.hooks.clone()),
+		("release.tasks", release_tasks),
+	] {
+		for hook in hooks.unwrap_or_default() {
+			if !names.contains(&hook) {
+				problems.push((
+					format!(
+						"`{}` runs `{}`, which isn't one of the custom_tasks",
+						key, hook
+					),
+					Some((None, hook)),
+				));
+			}
+		}
+	}
+
+	let mut templates = vec![config.clone()];
+	for template in config
+		.templates
+		.iter()
+		.flat_map(|templates| templates.values())
+	{
+		let mut templated = config.clone();
+		apply_template(&mut templated, template);
+		templates.push(templated);
+	}
+	for templated in templates {
+		let tokens = match tokenize(&templated.commit_message) {
+			Ok(tokens) => tokens,
+			Err(err) => {
+				problems.push((
+					format!(
+						"The template `{}` is invalid: {}",
+						templated.commit_message, err
+					),
+					Some((Some("commit_message"), templated.commit_message.clone())),
+				));
+				continue;
+			}
+		};
+		let highest = highest_index(&templated, &tokens);
+		for arg in templated.commit_message_arguments.iter().flatten() {
+			let type_enums = match arg.type_enums {
+				Some(ref type_enums) => type_enums,
+				None => continue,
+			};
+			let problem = format!(
+				"{} has type_enums, but `{}` only goes up to ${}",
+				arg.label(),
+				templated.commit_message,
+				highest
+			);
+			if arg.argument as usize > highest
+				&& !problems.iter().any(|(existing, _)| *existing == problem)
+			{
+				problems.push((
+					problem,
+					type_enums.first().map(|value| (None, value.clone())),
+				));
+			}
+		}
+	}
+
+	for task in &tasks {
+		for cmd in task.execute.iter().flatten() {
+			let binary = cmd.split(' ').next().unwrap_or_default();
+			if which::which(binary).is_err() {
+				problems.push((
+					format!(
+						"The task `{}` runs `{}`, which isn't on your PATH",
+						task.name, binary
+					),
+					Some((None, cmd.clone())),
+				));
+			}
+		}
+	}
+	problems
+}
+
+// the last place `needle` shows up, later files override earlier ones
+fn locate(
+	files: &[(PathBuf, String, Value)],
+	needle: &Needle,
+) -> Option<(PathBuf, (usize, usize))> {
+	let (key, value) = needle;
+	files.iter().rev().find_map(|(path, content, _)| {
+		let mut last = None;
+		let mut from = 0;
+		while let Some(offset) = find_token(content, value, from) {
+			from = offset + 1;
+			let line = content[..offset].rsplit('\n').next().unwrap_or_default();
+			if key.is_none_or(|key| find_token(line, key, 0).is_some()) {
+				last = Some(offset);
+			}
+		}
+		last.map(|offset| (path.clone(), position(content, offset)))
+	})
+}
+
+pub fn check(rc_path: &Path) -> anyhow::Result<Vec<Diagnostic>> {
+	check_in(rc_path, &std::env::current_dir()?, config_dir().as_deref())
+}
+
+// every problem in the glitterrc found from `cwd` and the global config in `config_dir`
+fn check_in(
+	rc_path: &Path,
+	cwd: &Path,
+	config_dir: Option<&Path>,
+) -> anyhow::Result<Vec<Diagnostic>> {
+	let layers = layers(rc_path, cwd, config_dir);
+	let mut files = Vec::new();
+	let mut diagnostics = Vec::new();
+	let mut seen = Vec::new();
+	for layer in &layers {
+		check_file(layer, config_dir, &mut files, &mut seen, &mut diagnostics);
+	}
+	let last = match layers.last() {
+		Some(last) => last.clone(),
+		None => return Ok(diagnostics),
+	};
+
+	let mut merged = Value::Object(Map::new());
+	for (_, _, value) in &files {
+		merge(&mut merged, value.clone())?;
+	}
+	let has_template = merged.get("commit_message").is_some();
+	if let Ok(config) = serde_json::from_value(merged) {
+		for (message, needle) in problems(&apply_preset(config, has_template)) {
+			let (file, position) = match needle.and_then(|needle| locate(&files, &needle)) {
+				Some((file, position)) => (file, Some(position)),
+				None => (last.clone(), None),
+			};
+			diagnostics.push(Diagnostic {
+				file,
+				position,
+				message,
+			});
+		}
+	}
+	// anything else that stops glitter from loading the config, eg. configs extending each other
+	if diagnostics.is_empty() {
+		if let Err(err) = parse(rc_path, config_dir) {
+			diagnostics.push(Diagnostic {
+				file: last,
+				position: None,
+				message: format!("{:#}", err),
+			});
+		}
+	}
+	Ok(diagnostics)
+}
+
+pub fn config(args: Arguments) -> anyhow::Result<()> {
+	match args.arguments.first().map(String::as_str) {
+		Some("check") => {
+			let diagnostics = check(&args.rc_path)?;
+			for diagnostic in &diagnostics {
+				println!("{} {}", "✖".red(), diagnostic);
+			}
+			if !diagnostics.is_empty() {
+				return Err(anyhow::Error::new(Error::new(
+					std::io::ErrorKind::InvalidInput,
+					format!(
+						"{} Found {} problem{} in the glitterrc.",
+						"Fatal".red(),
+						diagnostics.len(),
+						if diagnostics.len() == 1 { "" } else { "s" }
+					),
+				)));
+			}
+			println!("{} The glitterrc looks good.", "✔".green());
+			Ok(())
+		}
+		_ => Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			"Try `config check`.",
+		))),
+	}
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use crate::config::{CommitMessageArguments, CustomTaskOptions, GlitterRc};
+
+	use super::{check_file, check_in, find_token, position, problems};
+
+	#[test]
+	fn finds_keys_and_values() {
+		let toml =
+			"hooks = [\"fmt\"]\n\n[[custom_tasks]]\nname = \"fmt\"\nexecute = [\"cargo fmt\"]\n";
+		let first = find_token(toml, "fmt", 0).unwrap();
+		assert_eq!(position(toml, first), (1, 11));
+		let second = find_token(toml, "fmt", first + 1).unwrap();
+		assert_eq!(position(toml, second), (4, 9));
+		assert!(find_token(toml, "fmt", second + 1).is_none());
+
+		let yaml = "hooks:\n  - fmt\ncustom_tasks:\n  - name: fmt\n    execute: [cargo fmt]\n";
+		let hook = find_token(yaml, "fmt", 0).unwrap();
+		assert_eq!(position(yaml, hook), (2, 5));
+		let name = find_token(yaml, "fmt", hook + 1).unwrap();
+		assert_eq!(position(yaml, name), (4, 11));
+		assert!(find_token(yaml, "fmt", name + 1).is_none());
+		assert_eq!(
+			position(yaml, find_token(yaml, "custom_tasks", 0).unwrap()),
+			(3, 1)
+		);
+	}
+
+	#[test]
+	fn reports_unknown_keys_and_cases() {
+		let dir = std::env::temp_dir().join(format!("glitter-check-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join(".glitterrc");
+		fs::write(
+			&path,
+			r#"{
+	"commit_message": "$1: $2+",
+	"commit_message_arguments": [
+		{ "argument": 1, "case": "lowr", "type_enums": ["fix"] }
+	],
+	"custom_task": [],
+	"hooks+": ["fmt"]
+}"#,
+		)
+		.unwrap();
+
+		let (mut files, mut seen, mut diagnostics) = (Vec::new(), Vec::new(), Vec::new());
+		check_file(&path, None, &mut files, &mut seen, &mut diagnostics);
+		let found = diagnostics
+			.iter()
+			.map(|diagnostic| {
+				(
+					diagnostic.position,
+					diagnostic.message.split(',').next().unwrap(),
+				)
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			found,
+			vec![
+				(Some((4, 29)), "Unknown case: unknown variant `lowr`"),
+				(Some((6, 3)), "Unknown key `custom_task`")
+			]
+		);
+		assert_eq!(files.len(), 1);
+		assert!(diagnostics[1]
+			.to_string()
+			.ends_with(".glitterrc:6:3: Unknown key `custom_task`"));
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn checks_tasks_and_arguments() {
+		let task = |name: &str, cmd: &str| CustomTaskOptions {
+			name: name.to_owned(),
+			execute: Some(vec![cmd.to_owned()]),
+		};
+		let config = GlitterRc {
+			commit_message: "$1: $2+".to_string(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 3,
+				type_enums: Some(vec!["api".to_owned()]),
+				..Default::default()
+			}]),
+			custom_tasks: Some(vec![
+				task("fmt", "git status"),
+				task("fmt", "git diff"),
+				task("lint", "glitter-missing-binary --check"),
+			]),
+			hooks: Some(vec!["fmt".to_owned(), "test".to_owned()]),
+			..Default::default()
+		};
+		assert_eq!(
+			problems(&config),
+			vec![
+				(
+					"The task `fmt` is defined 2 times".to_owned(),
+					Some((Some("name"), "fmt".to_owned()))
+				),
+				(
+					"`hooks` runs `test`, which isn't one of the custom_tasks".to_owned(),
+					Some((None, "test".to_owned()))
+				),
+				(
+					"Argument 3 has type_enums, but `$1: $2+` only goes up to $2".to_owned(),
+					Some((None, "api".to_owned()))
+				),
+				(
+					"The task `lint` runs `glitter-missing-binary`, which isn't on your PATH"
+						.to_owned(),
+					Some((None, "glitter-missing-binary --check".to_owned()))
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn keeps_checking_after_type_errors() {
+		let dir = std::env::temp_dir().join(format!("glitter-check-types-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join(".glitterrc.toml");
+		fs::write(
+			&path,
+			r#"commit_message = "$1: $2+"
+verbose = "yes"
+hooks = ["lnt"]
+
+[[commit_message_arguments]]
+argument = 1
+transforms = ["trim", "shout"]
+
+[[commit_message_arguments]]
+argument = 5
+type_enums = ["api"]
+
+[[custom_task]]
+name = "lint"
+"#,
+		)
+		.unwrap();
+
+		let found = check_in(&dir.join(".glitterrc"), &dir, None)
+			.unwrap()
+			.iter()
+			.map(|diagnostic| {
+				(
+					diagnostic.position,
+					diagnostic.message.split(',').next().unwrap().to_owned(),
+				)
+			})
+			.collect::<Vec<_>>();
+		let expected = [
+			((7, 24), "Unknown transform: unknown variant `shout`"),
+			((2, 1), "Invalid `verbose`: invalid type: string \"yes\""),
+			((13, 3), "Unknown key `custom_task`"),
+			((3, 11), "`hooks` runs `lnt`"),
+			((11, 16), "Argument 5 has type_enums"),
+		];
+		assert_eq!(
+			found,
+			expected
+				.iter()
+				.map(|(position, message)| (Some(*position), message.to_string()))
+				.collect::<Vec<_>>()
+		);
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
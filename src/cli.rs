@@ -1,11 +1,24 @@
-use crate::config::{Arguments, CustomTaskOptions, GlitterRc};
+use crate::changelog::changelog;
+use crate::check;
+use crate::config::{
+	Arguments, Case, CommitMessageArguments, CustomTaskOptions, GlitterRc, Preset, Template,
+	Transform,
+};
+use crate::conventional;
+use crate::editor;
+use crate::hooks;
+use crate::lint::lint;
+use crate::release::release;
+use crate::template::{tokenize, Key, Token};
+use crate::wizard::{is_missing_arguments, prompt};
 use colored::*;
 use fancy_regex::Regex;
 use inflector::Inflector;
 use ms::*;
 use spinoff::{Spinner, Spinners};
+use std::collections::HashMap;
 use std::convert::TryInto;
-use std::io::{stdin, Error};
+use std::io::{stdin, Error, IsTerminal};
 use std::path::Path;
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -20,132 +33,658 @@ macro_rules! match_patterns {
     }
   }
 
-fn get_commit_message(config: &GlitterRc, args: &Arguments) -> anyhow::Result<String> {
-	let splitted = config.commit_message.split('$').skip(1);
+// map a placeholder onto the (1-based) position of the argument it refers to
+// names that aren't bound in commit_message_arguments give `None`, they may be variables instead
+pub(crate) fn argument_index(config: &GlitterRc, key: &Key) -> Option<usize> {
+	match key {
+		Key::Index(idx) => Some(*idx),
+		Key::Name(name) => config
+			.commit_message_arguments
+			.iter()
+			.flatten()
+			.find(|arg| arg.name.as_deref() == Some(name.as_str()) && arg.argument > 0)
+			.map(|arg| arg.argument as usize),
+	}
+}
+
+// levenshtein distance, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<char>>();
+	let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+
+	for (i, a_char) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, b_char) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(a_char != *b_char);
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
+// the closest valid value, if it's close enough to be what was meant
+fn suggest<'a>(value: &str, valid: &'a [String]) -> Option<&'a String> {
+	let value = value.to_lowercase();
+	valid
+		.iter()
+		.map(|candidate| (edit_distance(&value, &candidate.to_lowercase()), candidate))
+		.filter(|(distance, _)| *distance <= 2.max(value.chars().count() / 2))
+		.min_by_key(|(distance, _)| *distance)
+		.map(|(_, candidate)| candidate)
+}
+
+// check a value against the type_enums, pattern and length limits of its argument.
+// type_enums only make sense for a single word, so the joined value of a rest argument skips them
+pub(crate) fn validate_argument(
+	arg: &CommitMessageArguments,
+	value: &str,
+	rest: bool,
+) -> anyhow::Result<()> {
+	if let Some(valid_type_enums) = arg.type_enums.as_ref().filter(|_| !rest) {
+		if !valid_type_enums.iter().any(|valid| valid == value) {
+			let suggestion = match suggest(value, valid_type_enums) {
+				Some(closest) => format!(" Did you mean `{}`?", closest.green()),
+				None => String::new(),
+			};
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"{} `{}` did not have a valid type enum.{} Valid type enums are {}",
+					arg.label(),
+					value,
+					suggestion,
+					valid_type_enums.join(", ").red()
+				),
+			)));
+		}
+	}
+	if let Some(ref pattern) = arg.pattern {
+		if !Regex::new(&format!("^(?:{})$", pattern))?.is_match(value)? {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"{} `{}` doesn't match the pattern {}",
+					arg.label(),
+					value,
+					pattern.red()
+				),
+			)));
+		}
+	}
+	if arg.no_trailing_period.unwrap_or(false) && value.ends_with('.') {
+		return Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!("{} `{}` should not end with a period", arg.label(), value),
+		)));
+	}
+	if arg.imperative.unwrap_or(false) {
+		let first_word = value.split_whitespace().next().unwrap_or_default();
+		if !looks_imperative(first_word) {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"{} should start with an imperative verb (`add`, not `adds`, `added` or `adding`), but starts with `{}`",
+					arg.label(),
+					first_word.red()
+				),
+			)));
+		}
+	}
+	let length = value.chars().count();
+	if let Some(min_length) = arg.min_length {
+		if length < min_length {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"{} `{}` is {} characters long, it must be at least {}",
+					arg.label(),
+					value,
+					length,
+					min_length.to_string().red()
+				),
+			)));
+		}
+	}
+	if let Some(max_length) = arg.max_length {
+		if length > max_length {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"{} `{}` is {} characters long, it must be at most {}",
+					arg.label(),
+					value,
+					length,
+					max_length.to_string().red()
+				),
+			)));
+		}
+	}
+	Ok(())
+}
+
+pub(crate) fn convert_case(value: &str, case: Case) -> String {
+	match case {
+		Case::Lower => value.to_lowercase(),
+		Case::Upper => value.to_uppercase(),
+		Case::Camel => value.to_camel_case(),
+		Case::Class => value.to_class_case(),
+		Case::Pascal => value.to_pascal_case(),
+		Case::Snake => value.to_snake_case(),
+		Case::ScreamingSnake => value.to_screaming_snake_case(),
+		Case::Kebab => value.to_kebab_case(),
+		Case::Train => value.to_train_case(),
+		Case::Sentence => value.to_sentence_case(),
+		Case::Title => value.to_title_case(),
+		Case::Table => value.to_table_case(),
+		Case::ForeignKey => value.to_foreign_key(),
+	}
+}
+
+// pictographs, dingbats, flags and the joiners / variation selectors that glue emoji together
+fn is_emoji(c: char) -> bool {
+	matches!(
+		c as u32,
+		0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D
+	)
+}
+
+fn apply_transform(value: &str, transform: &Transform) -> anyhow::Result<String> {
+	Ok(match transform {
+		Transform::Trim => value.trim().to_owned(),
+		Transform::Case(case) => convert_case(value, *case),
+		Transform::Truncate(length) => value.chars().take(*length).collect(),
+		Transform::Prefix(prefix) => format!("{}{}", prefix, value),
+		Transform::Suffix(suffix) => format!("{}{}", value, suffix),
+		Transform::Replace { pattern, with } => Regex::new(pattern)?
+			.replace_all(value, with.as_str())
+			.into_owned(),
+		Transform::StripEmoji => value
+			.chars()
+			.filter(|c| !is_emoji(*c))
+			.collect::<String>()
+			.split_whitespace()
+			.collect::<Vec<&str>>()
+			.join(" "),
+	})
+}
+
+// verbs whose imperative form happens to end in `ed`, `s` or `ing`
+const IMPERATIVE_EXCEPTIONS: [&str; 15] = [
+	"shed", "shred", "spread", "thread", "wed", "bed", "alias", "bias", "canvas", "bring",
+	"spring", "string", "sting", "fling", "swing",
+];
+
+// a rough check for `add` vs `adds` / `added` / `adding`
+fn looks_imperative(word: &str) -> bool {
+	let word = word.to_lowercase();
+	if IMPERATIVE_EXCEPTIONS.contains(&word.as_str()) {
+		return true;
+	}
+	let length = word.chars().count();
+	let ing = length > 5 && word.ends_with("ing") && !word.ends_with("string");
+	let ed =
+		length > 3 && word.ends_with("ed") && !word.ends_with("eed") && !word.ends_with("embed");
+	let s = length > 3
+		&& word.ends_with('s')
+		&& !["ss", "us", "is", "ys"]
+			.iter()
+			.any(|end| word.ends_with(end));
+	!(ing || ed || s)
+}
+
+// apply the case conversions, transforms, aliases and validation configured for argument `idx` to a single value
+pub(crate) fn format_argument(
+	config: &GlitterRc,
+	idx: usize,
+	value: &str,
+	rest: bool,
+) -> anyhow::Result<String> {
+	let mut val_ = value.to_owned();
+	if let Some(ref args_) = config.commit_message_arguments {
+		for arg in args_.iter().filter(|arg| arg.argument == idx as i32) {
+			if let Some(case) = arg.word_case {
+				val_ = val_
+					.split(' ')
+					.map(|word| convert_case(word, case))
+					.collect::<Vec<String>>()
+					.join(" ");
+			}
+			if let Some(case) = arg.case {
+				val_ = convert_case(&val_, case);
+			}
+			for transform in arg.transforms.iter().flatten() {
+				val_ = apply_transform(&val_, transform)?;
+			}
+			if let Some(expanded) = arg.aliases.as_ref().and_then(|aliases| aliases.get(&val_)) {
+				val_ = expanded.clone();
+			}
+			validate_argument(arg, &val_, rest)?;
+		}
+	}
+	Ok(val_)
+}
 
-	let mut result = String::from(&config.commit_message);
+// render a list of tokens. when `required` is false a missing argument isn't an error,
+// instead the whole list renders to `None` so the surrounding `[...]` group can vanish
+fn render_tokens(
+	config: &GlitterRc,
+	args: &Arguments,
+	variables: &HashMap<String, String>,
+	tokens: &[Token],
+	required: bool,
+) -> anyhow::Result<Option<String>> {
+	let mut result = String::new();
+
+	for token in tokens {
+		match token {
+			Token::Literal(text) => result.push_str(text),
+			Token::Group(group) => {
+				if let Some(text) = render_tokens(config, args, variables, group, false)? {
+					result.push_str(&text);
+				}
+			}
+			Token::Argument { key, rest, default } => {
+				// empty values (`""`) count as not provided, so optional ones can be skipped
+				let (value, missing) = match (argument_index(config, key), key) {
+					(Some(idx), _) if *rest => {
+						let rest = args.arguments.get(idx - 1..).unwrap_or_default();
+						(
+							if rest.iter().any(|arg| !arg.is_empty()) {
+								Some(format_argument(config, idx, &rest.join(" "), true)?)
+							} else {
+								None
+							},
+							format!(
+								"Argument {0} was not provided. Argument {0} is a rest argument.",
+								idx
+							),
+						)
+					}
+					(Some(idx), _) => (
+						match args.arguments.get(idx - 1) {
+							Some(arg) if !arg.is_empty() => {
+								Some(format_argument(config, idx, arg, false)?)
+							}
+							_ => None,
+						},
+						format!("Argument {} was not provided.", idx),
+					),
+					(None, Key::Name(name)) => match variables.get(name) {
+						Some(value) => (
+							Some(value.clone()).filter(|value| !value.is_empty()),
+							format!("`${}` has no value in this repository.", name),
+						),
+						None => {
+							let mut known = variables.keys().cloned().collect::<Vec<String>>();
+							known.sort();
+							return Err(anyhow::Error::new(Error::new(
+								std::io::ErrorKind::InvalidInput,
+								format!(
+									"Argument `{}` is not bound to a position. Give it a `name` in commit_message_arguments or use one of the variables: {}",
+									name,
+									known.join(", ")
+								),
+							)));
+						}
+					},
+					(None, Key::Index(_)) => unreachable!(),
+				};
 
-	for val in splitted {
-		if val.len() >= 2 && String::from(val.chars().nth(1).unwrap()) == *"+" {
-			let idx = val.chars().next().unwrap().to_digit(10).unwrap() - 1;
-			let rest = &args.arguments[idx as usize..];
+				match (value, default) {
+					(Some(value), _) => result.push_str(&value),
+					(None, Some(default)) => result.push_str(default),
+					(None, None) if !required => return Ok(None),
+					(None, None) => {
+						return Err(anyhow::Error::new(Error::new(
+							std::io::ErrorKind::InvalidInput,
+							missing,
+						)))
+					}
+				}
+			}
+		}
+	}
+	Ok(Some(result))
+}
 
-			if rest.is_empty() {
+// swap in the template picked with `--template`, or the one called `default` if there is one
+pub(crate) fn select_template(
+	mut config: GlitterRc,
+	name: Option<&str>,
+) -> anyhow::Result<GlitterRc> {
+	let templates = config.templates.clone().unwrap_or_default();
+	let template = match name {
+		Some(name) => match templates.get(name) {
+			Some(template) => template,
+			None => {
+				let mut available = templates.keys().cloned().collect::<Vec<String>>();
+				available.sort();
 				return Err(anyhow::Error::new(Error::new(
 					std::io::ErrorKind::InvalidInput,
 					format!(
-						"Argument {0} was not provided. Argument {0} is a rest argument.",
-						val.split("").collect::<Vec<_>>()[1]
+						"There is no template called `{}`. Available templates are {}",
+						name,
+						available.join(", ").red()
 					),
 				)));
 			}
+		},
+		None => match templates.get("default") {
+			Some(template) => template,
+			None => return Ok(config),
+		},
+	};
 
-			result = result.replace(
-				&format!("${}+", val.split("").collect::<Vec<_>>()[1]),
-				&rest.join(" "),
-			);
-		} else {
-			let idx = val.split("").nth(1).unwrap().parse::<usize>().unwrap() - 1;
+	apply_template(&mut config, template);
+	Ok(config)
+}
+
+// switch to the `type_templates` entry matching the value of its argument, eg. a `docs` commit
+// without a scope. the value is validated first so aliases and cases are already applied
+pub(crate) fn select_type_template(
+	mut config: GlitterRc,
+	args: &Arguments,
+) -> anyhow::Result<GlitterRc> {
+	let typed = config
+		.commit_message_arguments
+		.iter()
+		.flatten()
+		.find(|arg| arg.type_templates.is_some() && arg.argument > 0)
+		.cloned();
+
+	if let Some(arg) = typed {
+		let idx = arg.argument as usize;
+		if let Some(value) = args
+			.arguments
+			.get(idx - 1)
+			.filter(|value| !value.is_empty())
+		{
+			let value = format_argument(&config, idx, value, false)?;
+			if let Some(template) = arg.type_templates.unwrap_or_default().get(&value) {
+				apply_template(&mut config, template);
+			}
+		}
+	}
+	Ok(config)
+}
+
+pub(crate) fn apply_template(config: &mut GlitterRc, template: &Template) {
+	config.commit_message = template.commit_message.clone();
+	if template.commit_message_arguments.is_some() {
+		config.commit_message_arguments = template.commit_message_arguments.clone();
+	}
+	if template.body.is_some() {
+		config.body = template.body.clone();
+	}
+	if template.footer.is_some() {
+		config.footer = template.footer.clone();
+	}
+}
+
+fn get_commit_message(
+	config: &GlitterRc,
+	args: &Arguments,
+	variables: &HashMap<String, String>,
+) -> anyhow::Result<String> {
+	let tokens = tokenize(&config.commit_message)?;
+	Ok(render_tokens(config, args, variables, &tokens, true)?.unwrap_or_default())
+}
+
+// everything after the subject: the body template, the paragraphs given after `--`, then the footer
+fn get_commit_paragraphs(
+	config: &GlitterRc,
+	args: &Arguments,
+	variables: &HashMap<String, String>,
+) -> anyhow::Result<Vec<String>> {
+	let mut paragraphs: Vec<String> = Vec::new();
+
+	if let Some(ref body) = config.body {
+		paragraphs.push(
+			render_tokens(config, args, variables, &tokenize(body)?, true)?.unwrap_or_default(),
+		);
+	}
+	paragraphs.extend(args.body.iter().cloned());
+	if let Some(ref footer) = config.footer {
+		paragraphs.push(
+			render_tokens(config, args, variables, &tokenize(footer)?, true)?.unwrap_or_default(),
+		);
+	}
+
+	Ok(paragraphs
+		.into_iter()
+		.map(|paragraph| paragraph.trim().to_owned())
+		.filter(|paragraph| !paragraph.is_empty())
+		.collect())
+}
+
+// trimmed stdout of a git command, or an empty string if it fails (no commits yet, no remote, etc.)
+pub(crate) fn git_output(args: &[&str]) -> String {
+	Command::new("git")
+		.args(args)
+		.output()
+		.ok()
+		.filter(|output| output.status.success())
+		.map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+		.unwrap_or_default()
+}
+
+// variables every template can use, looked up once per commit
+fn repository_variables(current_branch: &str) -> HashMap<String, String> {
+	let mut variables = HashMap::new();
+	variables.insert("branch".to_owned(), current_branch.trim().to_owned());
+	variables.insert("author".to_owned(), git_output(&["config", "user.name"]));
+	variables.insert("email".to_owned(), git_output(&["config", "user.email"]));
+	variables.insert(
+		"date".to_owned(),
+		format_date((get_current_epoch() / 1000) as u64),
+	);
+	variables.insert(
+		"short_sha_parent".to_owned(),
+		git_output(&["rev-parse", "--short", "HEAD"]),
+	);
+	variables.insert(
+		"remote".to_owned(),
+		git_output(&["remote", "get-url", "origin"]),
+	);
+	variables
+}
+
+// named groups of `branch_pattern` (eg. `(?P<ticket>[A-Z]+-\d+)`) become template variables.
+// when the branch doesn't match they're still defined, just empty, so `[...]` groups can drop them
+fn branch_variables(
+	config: &GlitterRc,
+	current_branch: &str,
+	variables: &mut HashMap<String, String>,
+) -> anyhow::Result<()> {
+	if let Some(ref pattern) = config.branch_pattern {
+		let regex = Regex::new(pattern)?;
+		let captures = regex.captures(current_branch.trim())?;
+
+		if captures.is_none() && config.require_branch_match.unwrap_or(false) {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"{} The branch `{}` doesn't match the branch_pattern `{}`.",
+					"Fatal".red(),
+					current_branch.trim(),
+					pattern
+				),
+			)));
+		}
+
+		for name in regex.capture_names().flatten() {
+			let value = captures
+				.as_ref()
+				.and_then(|captures| captures.name(name))
+				.map(|capture| capture.as_str().to_owned())
+				.unwrap_or_default();
+			variables.insert(name.to_owned(), value);
+		}
+	}
+	Ok(())
+}
 
-			if args.arguments.len() <= idx {
+// YYYY-MM-DD (UTC) for a unix timestamp in seconds
+pub(crate) fn format_date(secs: u64) -> String {
+	// civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+	let z = (secs / 86400) as i64 + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + i64::from(month <= 2);
+	format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// `Key: value` lines for the glitterrc `trailers` and the `--trailer key=value` flags.
+// a flag replaces the configured trailer with the same key, configured ones without a value are left out
+fn trailers(
+	config: &GlitterRc,
+	args: &Arguments,
+	variables: &HashMap<String, String>,
+) -> anyhow::Result<Vec<String>> {
+	let mut flags: Vec<(String, String)> = Vec::new();
+	for trailer in &args.trailer {
+		match trailer.split_once('=') {
+			Some((key, value)) if !key.trim().is_empty() => {
+				flags.push((key.trim().to_owned(), value.trim().to_owned()))
+			}
+			_ => {
 				return Err(anyhow::Error::new(Error::new(
 					std::io::ErrorKind::InvalidInput,
 					format!(
-						"Argument {} was not provided.",
-						val.split("").collect::<Vec<_>>()[1]
+						"`{}` isn't a valid trailer. Trailers are passed as {}",
+						trailer,
+						"--trailer key=value".red()
 					),
-				)));
+				)))
 			}
+		}
+	}
 
-			let mut val_ = (&args.arguments[idx]).clone();
-			if let Some(ref args_) = config.commit_message_arguments {
-				for arg in args_.iter().as_ref() {
-					if arg.argument == ((idx + 1) as i32) {
-						if let Some(v) = arg.case.as_deref() {
-							// we do this to prevent binding errors
-							let mut temp_val = val_.clone();
-							match v.to_lowercase().as_str() {
-								"lower" => temp_val = temp_val.to_lowercase(),
-								"upper" => temp_val = temp_val.to_uppercase(),
-								"snake" => temp_val = temp_val.to_snake_case(),
-								"screaming-snake" => temp_val = temp_val.to_screaming_snake_case(),
-								"kebab" => temp_val = temp_val.to_kebab_case(),
-								"train" => temp_val = temp_val.to_train_case(),
-								"sentence" => temp_val = temp_val.to_sentence_case(),
-								"title" => temp_val = temp_val.to_title_case(),
-								"pascal" => temp_val = temp_val.to_pascal_case(),
-								_ => println!("Found invalid case `{}`", v),
-							}
-							val_ = temp_val
-						}
-					}
-				}
-			}
-			if let Some(ref args_) = config.commit_message_arguments {
-				for arg in args_.iter().as_ref() {
-					if arg.argument == ((idx + 1) as i32) {
-						if let Some(valid_type_enums) = arg.type_enums.as_ref() {
-							if !valid_type_enums.contains(&val_.to_owned()) {
-								return Err(anyhow::Error::new(Error::new(
-									std::io::ErrorKind::InvalidInput,
-									format!(
-										"Argument {} did not have a valid type enum. Valid type enums are {}",
-										val.split("").collect::<Vec<_>>()[1],
-                                        valid_type_enums.join(", ").red()
-									),
-								)));
-							}
-						}
-					}
-				}
+	let mut result: Vec<String> = Vec::new();
+	for trailer in config.trailers.iter().flatten() {
+		if flags
+			.iter()
+			.any(|(key, _)| key.eq_ignore_ascii_case(&trailer.key))
+		{
+			continue;
+		}
+		if let Some(ref value) = trailer.value {
+			if let Some(value) = render_tokens(config, args, variables, &tokenize(value)?, false)?
+				.filter(|value| !value.trim().is_empty())
+			{
+				result.push(format!("{}: {}", trailer.key, value.trim()));
 			}
+		}
+	}
+	result.extend(
+		flags
+			.into_iter()
+			.map(|(key, value)| format!("{}: {}", key, value)),
+	);
+	Ok(result)
+}
 
-			if let Some(ref args_) = config.commit_message_arguments {
-				for arg in args_.iter().as_ref() {
-					if arg.argument == ((idx + 1) as i32) {
-						if let Some(valid_type_enums) = arg.type_enums.as_ref() {
-							if !valid_type_enums.contains(&val_.to_owned()) {
-								return Err(anyhow::Error::new(Error::new(
-									std::io::ErrorKind::InvalidInput,
-									format!(
-										"Argument {} did not have a valid type enum. Valid type enums are {}",
-										val.split("").collect::<Vec<_>>()[1],
-                                        valid_type_enums.join(", ").red()
-									),
-								)));
-							}
-						}
-					}
+// keys of `required` trailers that aren't in the message
+pub(crate) fn missing_trailers(config: &GlitterRc, paragraphs: &[String]) -> Vec<String> {
+	config
+		.trailers
+		.iter()
+		.flatten()
+		.filter(|trailer| trailer.required.unwrap_or(false))
+		.filter(|trailer| {
+			let prefix = format!("{}:", trailer.key.to_lowercase());
+			!paragraphs
+				.iter()
+				.flat_map(|paragraph| paragraph.lines())
+				.any(|line| line.to_lowercase().starts_with(&prefix))
+		})
+		.map(|trailer| trailer.key.clone())
+		.collect()
+}
+
+// `Co-authored-by:` trailers for the `--with` aliases
+fn co_authors(config: &GlitterRc, aliases: &[String]) -> anyhow::Result<Vec<String>> {
+	let team = config.team.clone().unwrap_or_default();
+	let mut trailers: Vec<String> = Vec::new();
+	for alias in aliases.iter().map(|alias| alias.trim()) {
+		if alias.is_empty() {
+			continue;
+		}
+		match team.get(alias) {
+			Some(person) => {
+				let trailer = format!("Co-authored-by: {}", person);
+				if !trailers.contains(&trailer) {
+					trailers.push(trailer);
 				}
 			}
+			None if team.is_empty() => {
+				return Err(anyhow::Error::new(Error::new(
+					std::io::ErrorKind::InvalidInput,
+					format!(
+						"{} `{}` isn't in the team, the glitterrc doesn't have a {} yet.",
+						"Fatal".red(),
+						alias,
+						"team".red()
+					),
+				)))
+			}
+			None => {
+				let mut known = team.keys().cloned().collect::<Vec<String>>();
+				known.sort();
+				let suggestion = match suggest(alias, &known) {
+					Some(closest) => format!(" Did you mean `{}`?", closest.green()),
+					None => String::new(),
+				};
+				return Err(anyhow::Error::new(Error::new(
+					std::io::ErrorKind::InvalidInput,
+					format!(
+						"{} `{}` isn't in the team.{} Known aliases are {}",
+						"Fatal".red(),
+						alias,
+						suggestion,
+						known.join(", ").red()
+					),
+				)));
+			}
+		}
+	}
+	Ok(trailers)
+}
 
-			let captures = Regex::new(&format!(
-				"\\${}(?!\\+)",
-				val.split("").collect::<Vec<_>>()[1]
-			))?;
-
-			let res = result.clone();
-
-			// poor mans replace
-			for _ in 0..captures.captures_iter(&res).count() {
-				let res = result.clone();
-
-				let capture = captures
-					// when we replace, the value changes, so we rerun the logic
-					.captures_iter(&res)
-					.collect::<Vec<_>>()
-					// we dont use the loop index as since the new value excludes the previous match we dont need to
-					.get(0)
-					.unwrap()
-					.as_ref()
-					.unwrap()
-					.get(0)
-					.unwrap();
-				result.replace_range(capture.range(), &val_);
+// run custom tasks by name, in order
+pub(crate) fn run_tasks(config: &GlitterRc, names: &[String], dry: bool, verbose: bool) {
+	let tasks = config.custom_tasks.clone().unwrap_or_default();
+	for name in names {
+		let custom_task = tasks.iter().find(|task| &task.name == name);
+		if let Some(task) = custom_task {
+			for cmd in task.execute.clone().unwrap_or_default() {
+				let splitted = cmd.split(' ').collect::<Vec<&str>>();
+				run_cmd(
+					splitted.first().unwrap(),
+					splitted[1..].to_vec(),
+					dry,
+					verbose,
+					None,
+				);
 			}
+		} else {
+			println!("{} Couldn't find the custom task `{}`", "Fatal".red(), name);
+			std::process::exit(1);
 		}
 	}
-	Ok(result)
+}
+
+// run the custom tasks listed in `hooks`
+pub(crate) fn run_hooks(config: &GlitterRc, dry: bool, verbose: bool) {
+	if let Some(ref hooks) = config.hooks {
+		run_tasks(config, hooks, dry, verbose);
+	}
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -157,6 +696,8 @@ pub fn commit(
 	no_verify: bool,
 	verbose: bool,
 	no_add: bool,
+	interactive: bool,
+	breaking: bool,
 ) -> anyhow::Result<(u128, String)> {
 	let is_git_folder = Path::new(".git").exists();
 	if !is_git_folder {
@@ -165,34 +706,68 @@ pub fn commit(
 			format!("{} This is not a git repository.", "Fatal".red()),
 		)));
 	}
-	let current_branch = String::from_utf8(
-		Command::new("git")
-			.arg("branch")
-			.arg("--show-current")
-			.output()
-			.unwrap()
-			.stdout,
-	)
-	.unwrap();
+	let co_authors = co_authors(&config, &args.with)?;
+	let current_branch = git_output(&["branch", "--show-current"]);
+	let mut variables = repository_variables(&current_branch);
+	variables.insert(
+		"breaking".to_owned(),
+		if breaking { "!" } else { "" }.to_owned(),
+	);
+	if !raw {
+		branch_variables(&config, &current_branch, &mut variables)?;
+	}
 	let mut _result = String::new();
+	let mut paragraphs = args.body.clone();
+	let config = if raw {
+		config
+	} else {
+		select_template(config, args.template.as_deref())?
+	};
+	let args = if !raw
+		&& (interactive || (stdin().is_terminal() && is_missing_arguments(&config, &args)?))
+	{
+		prompt(&config, args, &variables, &mut stdin().lock())?
+	} else {
+		args
+	};
+	let config = if raw {
+		config
+	} else {
+		select_type_template(config, &args)?
+	};
 	if !raw {
-		_result = get_commit_message(&config, &args)?;
+		_result = get_commit_message(&config, &args, &variables)?;
+		paragraphs = get_commit_paragraphs(&config, &args, &variables)?;
 	} else {
-		let raw_args = args.clone();
 		_result = get_commit_message(
 			&GlitterRc {
 				commit_message: "$1+".to_owned(),
-				arguments: Some(vec![args]),
-				commit_message_arguments: None,
-				fetch: None,
-				custom_tasks: None,
-				__default: None,
-				hooks: None,
-				verbose: None,
+				arguments: Some(vec![args.clone()]),
+				..Default::default()
 			},
-			&raw_args,
+			&args,
+			&variables,
 		)?
 	}
+	if breaking && !raw && config.preset == Some(Preset::Conventional) {
+		paragraphs = conventional::with_breaking_footer(&_result, paragraphs);
+	}
+	paragraphs = conventional::append_trailers(paragraphs, &trailers(&config, &args, &variables)?);
+	paragraphs = conventional::append_trailers(paragraphs, &co_authors);
+	if let Some(key) = missing_trailers(&config, &paragraphs).first() {
+		return Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"{} The `{}` trailer is required. Pass it with {}",
+				"Fatal".red(),
+				key,
+				format!("--trailer {}=...", key).green()
+			),
+		)));
+	}
+	if !raw && config.preset == Some(Preset::Conventional) {
+		conventional::validate(&_result, &paragraphs)?;
+	}
 	let mut warnings: Vec<String> = Vec::new();
 	if no_verify {
 		warnings.push("(no-verify)".yellow().to_string());
@@ -222,11 +797,19 @@ pub fn commit(
 		),
 		warnings.join(" ")
 	);
-	// if they abort the process (cmd+c / ctrl+c), this will error and stop
-	// if they press enter the command will then start executing git commands
+	for paragraph in &paragraphs {
+		println!("\n{}", paragraph.green());
+	}
+	// enter (or y) commits, n aborts and e opens the message in an editor
 	if !dry {
-		let mut temp = String::new();
-		stdin().read_line(&mut temp)?;
+		(_result, paragraphs) = editor::confirm(
+			&config,
+			_result,
+			paragraphs,
+			!raw,
+			&mut stdin().lock(),
+			|subject, paragraphs| editor::edit(&config, subject, paragraphs),
+		)?;
 	}
 
 	let start = get_current_epoch();
@@ -235,57 +818,43 @@ pub fn commit(
 		if fetch {
 			run_cmd("git", vec!["fetch"], dry, verbose, None);
 		}
-	} // glitter hooks
-	if config.custom_tasks.is_some()
-		&& config.hooks.is_some()
-		&& !config.hooks.clone().unwrap().is_empty()
-	{
-		let tasks = &config.custom_tasks.unwrap();
-		let task_names = &tasks
-			.iter()
-			.map(|task| task.clone().name)
-			.collect::<Vec<String>>();
-		let hooks = &config.hooks.unwrap();
-		for hook in hooks.clone() {
-			if !task_names.contains(&hook) {
-				println!("{} Couldn't find the custom task `{}`", "Fatal".red(), hook);
-				std::process::exit(1);
-			}
-			let custom_task = &tasks.iter().find(|task| task.name == hook);
-			if let Some(task) = custom_task {
-				for cmd in task.execute.clone().unwrap() {
-					if !no_verify {
-						let splitted = cmd.split(' ').collect::<Vec<&str>>();
-						run_cmd(
-							splitted.first().unwrap(),
-							splitted[1..].to_vec(),
-							dry || no_verify,
-							verbose,
-							None,
-						);
-					}
-				}
-			} else {
-				println!("{} Couldn't find the custom task `{}`", "Fatal".red(), hook);
-				std::process::exit(1);
-			}
-		}
+	}
+	if !no_verify {
+		run_hooks(&config, dry, verbose);
 	}
 	if !no_add {
 		run_cmd("git", vec!["add", "."], dry, verbose, None);
 	}
+	// the hooks tasks have run and the message is validated, tell installed git hooks to skip.
+	// raw messages aren't validated, so the commit-msg hook still checks them
+	let envs: &[(&str, &str)] = if raw {
+		&[(hooks::TASKS_DONE, "1")]
+	} else {
+		&[(hooks::TASKS_DONE, "1"), (hooks::VALIDATED, "1")]
+	};
+	// every paragraph gets its own -m so git separates them with a blank line
 	let mut commit_args = vec!["commit", "-m", &_result];
+	for paragraph in &paragraphs {
+		commit_args.push("-m");
+		commit_args.push(paragraph);
+	}
 	if no_verify {
 		commit_args.push("--no-verify")
 	}
-	run_cmd(
+	run_cmd_with_env(
 		"git",
 		commit_args,
+		envs,
 		dry,
 		verbose,
 		Some(&*format!(
-			"git commit -m {}{}",
+			"git commit -m {}{}{}",
 			format_args!("{}{}{0}", "`".green(), _result.underline().green()),
+			if paragraphs.is_empty() {
+				String::new()
+			} else {
+				format!(" (+{} paragraphs)", paragraphs.len())
+			},
 			if no_verify { " --no-verify" } else { "" }
 		)),
 	);
@@ -302,8 +871,20 @@ pub fn push(
 	no_verify: bool,
 	verbose: bool,
 	no_add: bool,
+	interactive: bool,
+	breaking: bool,
 ) -> anyhow::Result<()> {
-	let (start, current_branch) = commit(config, args, dry, raw, no_verify, verbose, no_add)?;
+	let (start, current_branch) = commit(
+		config,
+		args,
+		dry,
+		raw,
+		no_verify,
+		verbose,
+		no_add,
+		interactive,
+		breaking,
+	)?;
 
 	let mut args = vec!["pull", "origin"];
 	args.push(current_branch.split('\n').next().unwrap());
@@ -373,7 +954,7 @@ pub fn action(input: Vec<&str>) -> anyhow::Result<()> {
 }
 
 pub fn cc(config: GlitterRc, args: Arguments, dry: bool, verbose: bool) -> anyhow::Result<()> {
-	if args.arguments.first().is_some() {
+	if !args.arguments.is_empty() {
 		match_patterns! { &*args.arguments.first().unwrap().to_lowercase(), patterns,
 			"list" => {
 				let mut cmds: Vec<CustomTaskOptions> = vec![];
@@ -470,6 +1051,8 @@ pub fn match_cmds(args: Arguments, config: GlitterRc) -> anyhow::Result<()> {
 	let no_verify = args.no_verify();
 	let verbose = args.verbose();
 	let no_add = args.no_add();
+	let interactive = args.interactive();
+	let breaking = args.breaking();
 	let verbose = if verbose.provided {
 		verbose.value
 	} else {
@@ -477,12 +1060,17 @@ pub fn match_cmds(args: Arguments, config: GlitterRc) -> anyhow::Result<()> {
 	};
 	// custom macro for the patterns command
 	match_patterns! { &*cmd.to_lowercase(), patterns,
-		"push" => push(config, args, dry, raw_mode, no_verify, verbose, no_add)?,
-		"commit" => commit(config, args, dry, raw_mode, no_verify, verbose, no_add).map(|_|())?,
+		"push" => push(config, args, dry, raw_mode, no_verify, verbose, no_add, interactive, breaking)?,
+		"commit" => commit(config, args, dry, raw_mode, no_verify, verbose, no_add, interactive, breaking).map(|_|())?,
 		"action" => action(patterns)?,
 		"actions" => action(patterns)?,
 		"cc" => cc(config, args, dry, verbose)?,
 		"undo" => undo(dry, verbose)?,
+		"lint" => lint(config, args)?,
+		"changelog" => changelog(config, args)?,
+		"release" => release(config, args, dry, verbose)?,
+		"hooks" => hooks::hooks(config, args, dry, verbose)?,
+		"config" => check::config(args)?,
 		_ => {
 				let mut cmds: Vec<CustomTaskOptions> = vec![];
 				let mut exec_cmds: Vec<CustomTaskOptions> = vec![];
@@ -534,9 +1122,21 @@ pub fn match_cmds(args: Arguments, config: GlitterRc) -> anyhow::Result<()> {
 	Ok(())
 }
 
-fn run_cmd(
+pub(crate) fn run_cmd(
+	command_name: &str,
+	args: Vec<&str>,
+	dry: bool,
+	verbose: bool,
+	spinner_message: Option<&str>,
+) {
+	run_cmd_with_env(command_name, args, &[], dry, verbose, spinner_message)
+}
+
+// `run_cmd` with extra environment variables for this command only
+pub(crate) fn run_cmd_with_env(
 	command_name: &str,
 	args: Vec<&str>,
+	envs: &[(&str, &str)],
 	dry: bool,
 	verbose: bool,
 	spinner_message: Option<&str>,
@@ -573,6 +1173,7 @@ fn run_cmd(
 		let mut command = Command::new(cmd_path);
 		command.args(&args);
 		command.envs(std::env::vars());
+		command.envs(envs.iter().copied());
 		let output = command
 			.stdout(std::process::Stdio::piped())
 			.output()
@@ -646,11 +1247,19 @@ fn get_current_epoch() -> u128 {
 mod tests {
 	use crate::cli::action;
 	use crate::match_cmds;
+	use std::collections::HashMap;
 	use std::path::PathBuf;
 
-	use crate::config::{Arguments, CommitMessageArguments, CustomTaskOptions, GlitterRc};
+	use crate::config::{
+		Arguments, Case, CommitMessageArguments, CustomTaskOptions, GlitterRc, Template,
+		TrailerOptions, Transform,
+	};
 
-	use super::get_commit_message;
+	use super::{
+		branch_variables, co_authors, edit_distance, format_date, get_commit_message,
+		get_commit_paragraphs, looks_imperative, missing_trailers, select_template,
+		select_type_template, trailers,
+	};
 
 	#[test]
 	fn basic() {
@@ -668,23 +1277,28 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
-		assert_eq!(get_commit_message(&config, &args).unwrap(), "test(a): b c")
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"test(a): b c"
+		)
 	}
 
 	#[test]
@@ -703,24 +1317,26 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+ : $2 | $1+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		assert_eq!(
-			get_commit_message(&config, &args).unwrap(),
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
 			"test(a): b c : a | test a b c"
 		)
 	}
@@ -736,6 +1352,13 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let args_2 = Arguments {
@@ -747,38 +1370,35 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		let config_2 = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
-		assert!(get_commit_message(&config, &args).is_err());
-		assert!(get_commit_message(&config_2, &args_2).is_err());
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+		assert!(get_commit_message(&config_2, &args_2, &HashMap::new()).is_err());
 	}
 
 	#[test]
@@ -792,24 +1412,26 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			// "$1+" is the default
 			commit_message: "$1+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
-		assert!(get_commit_message(&config, &args).is_ok())
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_ok())
 	}
 
 	#[test]
@@ -823,36 +1445,804 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1: $2: $3+".to_string(),
-			arguments: None,
 			commit_message_arguments: Some(vec![CommitMessageArguments {
 				argument: 1,
-				case: Some("snake".to_string()),
+				case: Some(Case::Snake),
 				type_enums: Some(vec![
 					"fix".to_owned(),
 					"feat".to_owned(),
 					"chore".to_owned(),
 				]),
+				..Default::default()
 			}]),
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		assert_eq!(
-			get_commit_message(&config, &args).unwrap(),
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
 			"feat: test: tests"
 		)
 	}
 
+	#[test]
+	fn named_arguments() {
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec![
+				"FEAT".to_string(),
+				"cli".to_string(),
+				"add".to_string(),
+				"names".to_string(),
+			],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+
+		let config = GlitterRc {
+			commit_message: "$type($scope): $subject+ | $1".to_string(),
+			commit_message_arguments: Some(vec![
+				CommitMessageArguments {
+					argument: 1,
+					name: Some("type".to_owned()),
+					case: Some(Case::Lower),
+					type_enums: Some(vec!["fix".to_owned(), "feat".to_owned()]),
+					..Default::default()
+				},
+				CommitMessageArguments {
+					argument: 2,
+					name: Some("scope".to_owned()),
+					..Default::default()
+				},
+				CommitMessageArguments {
+					argument: 3,
+					name: Some("subject".to_owned()),
+					..Default::default()
+				},
+			]),
+			..Default::default()
+		};
+
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"feat(cli): add names | feat"
+		);
+
+		let config = GlitterRc {
+			commit_message: "$type: $1+".to_string(),
+			..Default::default()
+		};
+
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+	}
+
+	#[test]
+	fn multi_digit_arguments() {
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: (1..=12).map(|i| i.to_string()).collect(),
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+
+		let config = GlitterRc {
+			commit_message: "$1 $$10 $10 $11+".to_string(),
+			..Default::default()
+		};
+
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"1 $10 10 11 12"
+		);
+
+		let config = GlitterRc {
+			commit_message: "$1: $?".to_string(),
+			..Default::default()
+		};
+
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+	}
+
+	#[test]
+	fn optional_arguments() {
+		let config = GlitterRc {
+			commit_message: "$1$[($2)]: ${3+:-wip}".to_string(),
+			..Default::default()
+		};
+
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string(), "cli".to_string(), "add".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"feat(cli): add"
+		);
+
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string(), "".to_string(), "add".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"feat: add"
+		);
+
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"feat: wip"
+		);
+
+		// brackets without a `$` in front are left alone, like before optional groups
+		let config = GlitterRc {
+			commit_message: "[WIP] $1+ ]".to_string(),
+			..config
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"[WIP] feat ]"
+		);
+	}
+
+	#[test]
+	fn body_and_footer() {
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string(), "cli".to_string(), "add".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec!["Explains why.".to_string(), " ".to_string()],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+
+		let config = GlitterRc {
+			commit_message: "$1: $3+".to_string(),
+			body: Some("$[Scope: $2]".to_string()),
+			footer: Some("Refs: $2".to_string()),
+			..Default::default()
+		};
+
+		assert_eq!(
+			get_commit_paragraphs(&config, &args, &HashMap::new()).unwrap(),
+			vec!["Scope: cli", "Explains why.", "Refs: cli"]
+		);
+	}
+
+	#[test]
+	fn repository_variables() {
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string(), "add".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+
+		let config = GlitterRc {
+			commit_message: "$1: $2+ ($branch)$[ on $date]$[ via $remote]".to_string(),
+			..Default::default()
+		};
+
+		let mut variables = HashMap::new();
+		variables.insert("branch".to_owned(), "PROJ-12-login".to_owned());
+		variables.insert("date".to_owned(), format_date(1660000000));
+		variables.insert("remote".to_owned(), "".to_owned());
+
+		assert_eq!(
+			get_commit_message(&config, &args, &variables).unwrap(),
+			"feat: add (PROJ-12-login) on 2022-08-08"
+		);
+
+		let config = GlitterRc {
+			commit_message: "$1: $2+ $ticket".to_string(),
+			..Default::default()
+		};
+
+		assert!(get_commit_message(&config, &args, &variables).is_err());
+	}
+
+	#[test]
+	fn branch_pattern() {
+		let config = GlitterRc {
+			commit_message: "$ticket: $1+".to_string(),
+			branch_pattern: Some("^(?:feature/)?(?P<ticket>[A-Z]+-\\d+)".to_string()),
+			require_branch_match: Some(true),
+			..Default::default()
+		};
+
+		let mut variables = HashMap::new();
+		branch_variables(&config, "feature/PROJ-42-login\n", &mut variables).unwrap();
+		assert_eq!(variables.get("ticket").unwrap(), "PROJ-42");
+
+		let mut variables = HashMap::new();
+		assert!(branch_variables(&config, "master", &mut variables).is_err());
+
+		let config = GlitterRc {
+			require_branch_match: None,
+			..config
+		};
+		let mut variables = HashMap::new();
+		branch_variables(&config, "master", &mut variables).unwrap();
+		assert_eq!(variables.get("ticket").unwrap(), "");
+	}
+
+	#[test]
+	fn argument_validation() {
+		let config = GlitterRc {
+			commit_message: "$1($scope): $3+".to_string(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 2,
+				name: Some("scope".to_owned()),
+				pattern: Some("[a-z]+(/v\\d+)?".to_owned()),
+				min_length: Some(2),
+				max_length: Some(8),
+				..Default::default()
+			}]),
+			..Default::default()
+		};
+
+		let mut args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string(), "api/v2".to_string(), "add".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"feat(api/v2): add"
+		);
+
+		args.arguments[1] = "API".to_string();
+		let err = get_commit_message(&config, &args, &HashMap::new())
+			.unwrap_err()
+			.to_string();
+		assert!(err.contains("Argument 2 (scope) `API`"), "{}", err);
+
+		args.arguments[1] = "a".to_string();
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+
+		args.arguments[1] = "database".to_string() + "s";
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+	}
+
+	#[test]
+	fn type_enum_aliases_and_suggestions() {
+		let mut aliases = HashMap::new();
+		aliases.insert("f".to_owned(), "feat".to_owned());
+		aliases.insert("bugfix".to_owned(), "fix".to_owned());
+
+		let config = GlitterRc {
+			commit_message: "$1: $2+".to_string(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 1,
+				case: Some(Case::Lower),
+				type_enums: Some(vec!["fix".to_owned(), "feat".to_owned()]),
+				aliases: Some(aliases),
+				..Default::default()
+			}]),
+			..Default::default()
+		};
+
+		let mut args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["BugFix".to_string(), "typo".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"fix: typo"
+		);
+
+		args.arguments[0] = "feature".to_string();
+		let err = get_commit_message(&config, &args, &HashMap::new())
+			.unwrap_err()
+			.to_string();
+		assert!(err.contains("Did you mean `"), "{}", err);
+		assert!(err.contains("feat"), "{}", err);
+
+		// the joined value of `$1+` isn't a single word, so the type enums don't apply to it
+		let config = GlitterRc {
+			commit_message: "$1 ($1+)".to_string(),
+			..config
+		};
+		args.arguments = vec!["fix".to_string(), "the".to_string(), "typo".to_string()];
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"fix (fix the typo)"
+		);
+
+		assert_eq!(edit_distance("feature", "feat"), 3);
+		assert_eq!(edit_distance("fxi", "fix"), 2);
+		assert_eq!(edit_distance("", "ci"), 2);
+	}
+
+	#[test]
+	fn rest_argument_rules() {
+		let config = GlitterRc {
+			commit_message: "$1: $2+".to_string(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 2,
+				case: Some(Case::Sentence),
+				max_length: Some(20),
+				no_trailing_period: Some(true),
+				imperative: Some(true),
+				..Default::default()
+			}]),
+			..Default::default()
+		};
+
+		let mut args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["feat".to_string(), "add".to_string(), "parser".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"feat: Add parser"
+		);
+
+		args.arguments[1] = "added".to_string();
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+
+		args.arguments[1] = "add".to_string();
+		args.arguments.push("and".to_string());
+		args.arguments.push("tokenizer".to_string());
+		assert!(get_commit_message(&config, &args, &HashMap::new()).is_err());
+
+		assert!(looks_imperative("address"));
+		assert!(looks_imperative("bring"));
+		assert!(looks_imperative("shed"));
+		assert!(looks_imperative("Spread"));
+		assert!(!looks_imperative("finished"));
+		assert!(!looks_imperative("fixes"));
+		assert!(!looks_imperative("Updating"));
+	}
+
+	#[test]
+	fn transform_pipeline() {
+		let config = GlitterRc {
+			commit_message: "$1: $2+".to_string(),
+			commit_message_arguments: Some(vec![
+				CommitMessageArguments {
+					argument: 1,
+					case: Some(Case::Class),
+					transforms: Some(vec![Transform::Prefix("#".to_owned())]),
+					..Default::default()
+				},
+				CommitMessageArguments {
+					argument: 2,
+					transforms: Some(vec![
+						Transform::StripEmoji,
+						Transform::Trim,
+						Transform::Replace {
+							pattern: "(?i)\\bjs\\b".to_owned(),
+							with: "JS".to_owned(),
+						},
+						Transform::Case(Case::Camel),
+						Transform::Truncate(10),
+						Transform::Suffix("!".to_owned()),
+					]),
+					..Default::default()
+				},
+			]),
+			..Default::default()
+		};
+
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec![
+				"user_accounts".to_string(),
+				"\u{2728} js".to_string(),
+				"bundle size".to_string(),
+			],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert_eq!(
+			get_commit_message(&config, &args, &HashMap::new()).unwrap(),
+			"#UserAccount: jsBundleSi!"
+		);
+	}
+
+	#[test]
+	fn named_templates() {
+		let mut templates = HashMap::new();
+		templates.insert(
+			"default".to_owned(),
+			Template {
+				commit_message: "$1: $2+".to_owned(),
+				commit_message_arguments: None,
+				body: None,
+				footer: None,
+			},
+		);
+		templates.insert(
+			"release".to_owned(),
+			Template {
+				commit_message: "release: v$1".to_owned(),
+				commit_message_arguments: Some(vec![]),
+				body: None,
+				footer: Some("$[Released by $2]".to_owned()),
+			},
+		);
+
+		let config = GlitterRc {
+			commit_message: "$1+".to_string(),
+			templates: Some(templates),
+			..Default::default()
+		};
+
+		let mut args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["1.2.0".to_string(), "ci".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			body: vec![],
+			template: None,
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+
+		let default = select_template(config, None).unwrap();
+		assert_eq!(
+			get_commit_message(&default, &args, &HashMap::new()).unwrap(),
+			"1.2.0: ci"
+		);
+
+		args.template = Some("release".to_owned());
+		let release = select_template(default, args.template.as_deref()).unwrap();
+		assert_eq!(
+			get_commit_message(&release, &args, &HashMap::new()).unwrap(),
+			"release: v1.2.0"
+		);
+		assert_eq!(
+			get_commit_paragraphs(&release, &args, &HashMap::new()).unwrap(),
+			vec!["Released by ci"]
+		);
+
+		assert!(select_template(release, Some("hotfix")).is_err());
+	}
+
+	#[test]
+	fn type_templates() {
+		let mut type_templates = HashMap::new();
+		type_templates.insert(
+			"docs".to_owned(),
+			Template {
+				commit_message: "$1: $2+".to_owned(),
+				commit_message_arguments: None,
+				body: None,
+				footer: None,
+			},
+		);
+		type_templates.insert(
+			"revert".to_owned(),
+			Template {
+				commit_message: "$1: $3+".to_owned(),
+				commit_message_arguments: None,
+				body: None,
+				footer: Some("This reverts commit $sha.".to_owned()),
+			},
+		);
+
+		let config = GlitterRc {
+			commit_message: "$1($2): $3+".to_string(),
+			commit_message_arguments: Some(vec![
+				CommitMessageArguments {
+					argument: 1,
+					case: Some(Case::Lower),
+					type_enums: Some(vec![
+						"feat".to_owned(),
+						"docs".to_owned(),
+						"revert".to_owned(),
+					]),
+					type_templates: Some(type_templates),
+					..Default::default()
+				},
+				CommitMessageArguments {
+					argument: 2,
+					name: Some("sha".to_owned()),
+					..Default::default()
+				},
+			]),
+			..Default::default()
+		};
+
+		let args = Arguments {
+			action: "push".to_string(),
+			arguments: vec!["Docs".to_string(), "fix".to_string(), "typo".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			body: vec![],
+			template: None,
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		let docs = select_type_template(config.clone(), &args).unwrap();
+		assert_eq!(
+			get_commit_message(&docs, &args, &HashMap::new()).unwrap(),
+			"docs: fix typo"
+		);
+
+		let args = Arguments {
+			arguments: vec![
+				"revert".to_string(),
+				"abc1234".to_string(),
+				"bad".to_string(),
+				"change".to_string(),
+			],
+			..args
+		};
+		let revert = select_type_template(config, &args).unwrap();
+		assert_eq!(
+			get_commit_message(&revert, &args, &HashMap::new()).unwrap(),
+			"revert: bad change"
+		);
+		assert_eq!(
+			get_commit_paragraphs(&revert, &args, &HashMap::new()).unwrap(),
+			vec!["This reverts commit abc1234."]
+		);
+	}
+
+	#[test]
+	fn co_author_trailers() {
+		let mut team = HashMap::new();
+		team.insert(
+			"alice".to_owned(),
+			"Alice Liddell <alice@example.com>".to_owned(),
+		);
+		team.insert("bob".to_owned(), "Bob Ross <bob@example.com>".to_owned());
+
+		let config = GlitterRc {
+			commit_message: "$1+".to_string(),
+			team: Some(team),
+			..Default::default()
+		};
+
+		assert_eq!(
+			co_authors(
+				&config,
+				&["alice".to_owned(), "bob".to_owned(), "alice".to_owned()]
+			)
+			.unwrap(),
+			vec![
+				"Co-authored-by: Alice Liddell <alice@example.com>",
+				"Co-authored-by: Bob Ross <bob@example.com>"
+			]
+		);
+		assert!(co_authors(&config, &[]).unwrap().is_empty());
+
+		let err = co_authors(&config, &["alcie".to_owned()])
+			.unwrap_err()
+			.to_string();
+		assert!(err.contains("alice"), "{}", err);
+	}
+
+	#[test]
+	fn configured_trailers() {
+		let config = GlitterRc {
+			commit_message: "$1+".to_string(),
+			trailers: Some(vec![
+				TrailerOptions {
+					key: "Signed-off-by".to_owned(),
+					value: Some("$author <$email>".to_owned()),
+					required: None,
+				},
+				TrailerOptions {
+					key: "Refs".to_owned(),
+					value: Some("$ticket".to_owned()),
+					required: Some(true),
+				},
+			]),
+			..Default::default()
+		};
+		let args = Arguments {
+			action: "commit".to_string(),
+			arguments: vec!["fix".to_string()],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		let mut variables = HashMap::new();
+		variables.insert("author".to_owned(), "Ann".to_owned());
+		variables.insert("email".to_owned(), "ann@example.com".to_owned());
+		variables.insert("ticket".to_owned(), String::new());
+
+		// `$ticket` is empty so the Refs trailer is left out, and it's required
+		let found = trailers(&config, &args, &variables).unwrap();
+		assert_eq!(found, vec!["Signed-off-by: Ann <ann@example.com>"]);
+		assert_eq!(missing_trailers(&config, &found), vec!["Refs"]);
+
+		let args = Arguments {
+			trailer: vec!["refs=#12".to_owned(), "Reviewed-by=Bo".to_owned()],
+			..args
+		};
+		let found = trailers(&config, &args, &variables).unwrap();
+		assert_eq!(
+			found,
+			vec![
+				"Signed-off-by: Ann <ann@example.com>",
+				"refs: #12",
+				"Reviewed-by: Bo"
+			]
+		);
+		assert!(missing_trailers(&config, &found).is_empty());
+
+		let args = Arguments {
+			trailer: vec!["Refs".to_owned()],
+			..args
+		};
+		assert!(trailers(&config, &args, &variables).is_err());
+	}
+
 	#[test]
 	fn test_action() {
 		assert!(action(vec!["test"]).is_ok())
@@ -874,20 +2264,22 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		assert!(match_cmds(args, config).is_ok());
@@ -906,20 +2298,22 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		assert!(match_cmds(args, config).is_ok());
@@ -938,20 +2332,22 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
-			commit_message_arguments: None,
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		assert!(match_cmds(args, config).is_err());
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use structopt::StructOpt;
 
 fn commit_msg() -> String {
@@ -41,6 +42,41 @@ pub struct Arguments {
 	/// don't run `git add .`
 	#[structopt(long = "no-add")]
 	pub(crate) no_add: Option<Option<bool>>,
+
+	/// mark the commit as a breaking change: adds `!` to conventional commits and a `BREAKING CHANGE:` footer
+	#[structopt(long, short)]
+	#[serde(default)]
+	pub(crate) breaking: Option<Option<bool>>,
+
+	/// prompt for each argument of the template. turned on automatically when arguments are missing in a terminal
+	#[structopt(long, short)]
+	#[serde(default)]
+	pub(crate) interactive: Option<Option<bool>>,
+
+	/// commit body paragraphs, passed after `--`. each one becomes its own paragraph
+	#[structopt(last = true)]
+	#[serde(default)]
+	pub body: Vec<String>,
+
+	/// which of the glitterrc `templates` to use. defaults to `default`
+	#[structopt(long, short)]
+	#[serde(default)]
+	pub template: Option<String>,
+
+	/// output format of `changelog`: `markdown` (the default) or `json`
+	#[structopt(long, short)]
+	#[serde(default)]
+	pub format: Option<String>,
+
+	/// aliases from the glitterrc `team` to add as co-authors, eg. `--with alice,bob`
+	#[structopt(long, short, use_delimiter = true, require_delimiter = true)]
+	#[serde(default)]
+	pub with: Vec<String>,
+
+	/// extra trailers for the footer, eg. `--trailer Refs=#12`. can be repeated
+	#[structopt(long = "trailer", number_of_values = 1)]
+	#[serde(default)]
+	pub trailer: Vec<String>,
 }
 
 pub struct VerboseResponse {
@@ -94,13 +130,146 @@ impl Arguments {
 			Some(Some(a)) => a,
 		}
 	}
+	pub fn breaking(&self) -> bool {
+		match self.breaking {
+			None => false,
+			Some(None) => true,
+			Some(Some(a)) => a,
+		}
+	}
+	pub fn interactive(&self) -> bool {
+		match self.interactive {
+			None => false,
+			Some(None) => true,
+			Some(Some(a)) => a,
+		}
+	}
+}
+
+// every case Inflector can convert to, plus plain lower and upper case.
+// names are matched case-insensitively, like `case` always was
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone, Copy)]
+#[serde(try_from = "String")]
+pub enum Case {
+	Lower,
+	Upper,
+	Camel,
+	Class,
+	Pascal,
+	Snake,
+	ScreamingSnake,
+	Kebab,
+	Train,
+	Sentence,
+	Title,
+	Table,
+	ForeignKey,
+}
+
+const CASES: [(&str, Case); 13] = [
+	("lower", Case::Lower),
+	("upper", Case::Upper),
+	("camel", Case::Camel),
+	("class", Case::Class),
+	("pascal", Case::Pascal),
+	("snake", Case::Snake),
+	("screaming-snake", Case::ScreamingSnake),
+	("kebab", Case::Kebab),
+	("train", Case::Train),
+	("sentence", Case::Sentence),
+	("title", Case::Title),
+	("table", Case::Table),
+	("foreign-key", Case::ForeignKey),
+];
+
+impl TryFrom<String> for Case {
+	type Error = String;
+
+	fn try_from(name: String) -> Result<Case, String> {
+		let lowercase = name.to_lowercase();
+		CASES
+			.iter()
+			.find(|(case_name, _)| *case_name == lowercase)
+			.map(|(_, case)| *case)
+			.ok_or_else(|| {
+				format!(
+					"unknown variant `{}`, expected one of {}",
+					name,
+					CASES
+						.iter()
+						.map(|(case_name, _)| format!("`{}`", case_name))
+						.collect::<Vec<String>>()
+						.join(", ")
+				)
+			})
+	}
+}
+
+// one step of an argument's `transforms`, eg. `"trim"` or `{ "truncate": 50 }`
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transform {
+	Trim,
+	Case(Case),
+	Truncate(usize),
+	Prefix(String),
+	Suffix(String),
+	Replace { pattern: String, with: String },
+	StripEmoji,
 }
 
-#[derive(Deserialize, Debug, Eq, PartialEq)]
+#[derive(Deserialize, Debug, Default, Eq, PartialEq, Clone)]
 pub struct CommitMessageArguments {
 	pub argument: i32,
-	pub case: Option<String>,
+	/// lets the template refer to this argument as `$name` instead of `$argument`
+	pub name: Option<String>,
+	/// shorthand for a `case` transform that runs before `transforms`
+	pub case: Option<Case>,
 	pub type_enums: Option<Vec<String>>,
+	/// regex the whole (case converted) value has to match
+	pub pattern: Option<String>,
+	pub min_length: Option<usize>,
+	pub max_length: Option<usize>,
+	/// shorthands expanded before validation, eg. `f` -> `feat`
+	pub aliases: Option<HashMap<String, String>>,
+	/// case applied to every word on its own, before `case`
+	pub word_case: Option<Case>,
+	/// reject values ending in `.`
+	pub no_trailing_period: Option<bool>,
+	/// reject values whose first word looks like `added`, `adds` or `adding` rather than `add`
+	pub imperative: Option<bool>,
+	/// run in order after `word_case` and `case`
+	pub transforms: Option<Vec<Transform>>,
+	/// templates to use instead of the current one when this argument has a given value
+	pub type_templates: Option<HashMap<String, Template>>,
+}
+
+impl CommitMessageArguments {
+	// how errors refer to this argument, eg. `Argument 2 (scope)`
+	pub fn label(&self) -> String {
+		match self.name {
+			Some(ref name) => format!("Argument {} ({})", self.argument, name),
+			None => format!("Argument {}", self.argument),
+		}
+	}
+}
+
+// built-in commit conventions a glitterrc can opt into
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+	/// https://www.conventionalcommits.org
+	Conventional,
+}
+
+// a named alternative to the top level commit_message, picked with `--template`
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct Template {
+	pub commit_message: String,
+	/// when left out the top level commit_message_arguments are used
+	pub commit_message_arguments: Option<Vec<CommitMessageArguments>>,
+	pub body: Option<String>,
+	pub footer: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -109,8 +278,43 @@ pub struct CustomTaskOptions {
 	pub execute: Option<Vec<String>>,
 }
 
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct ChangelogOptions {
+	/// section title for each type, eg. `"feat": "Added"`. types without one are left out
+	pub sections: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize, Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Bump {
+	Patch,
+	Minor,
+	Major,
+}
+
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct ReleaseOptions {
+	/// version bump for each type, eg. `"feat": "minor"`. breaking changes are always major
+	pub bumps: Option<HashMap<String, Bump>>,
+	/// custom tasks to run before the release is tagged
+	pub tasks: Option<Vec<String>>,
+	/// put in front of the version in tag names, defaults to `v`
+	pub tag_prefix: Option<String>,
+}
+
+// a `Key: value` line added to the footer of every commit
+#[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct TrailerOptions {
+	/// eg. `Refs` or `Signed-off-by`
+	pub key: String,
+	/// template for the value, rendered like `commit_message`. left out, it has to be passed with `--trailer`
+	pub value: Option<String>,
+	/// refuse to commit without this trailer
+	pub required: Option<bool>,
+}
+
 // main struct for the GlitterRc with defaults
-#[derive(Deserialize, Debug, Eq, PartialEq)]
+#[derive(Deserialize, Debug, Default, Eq, PartialEq, Clone)]
 pub struct GlitterRc {
 	#[serde(default = "commit_msg")]
 	pub commit_message: String,
@@ -121,13 +325,35 @@ pub struct GlitterRc {
 	pub hooks: Option<Vec<String>>,
 	pub __default: Option<bool>, // this really shouldn't be provided by a user, but it's here for backwards compatibility
 	pub verbose: Option<bool>,
+	/// template for the commit body, rendered with the same arguments as `commit_message`
+	pub body: Option<String>,
+	/// template for the commit footer, which always comes last
+	pub footer: Option<String>,
+	/// regex run against the current branch, its named groups become template variables
+	pub branch_pattern: Option<String>,
+	/// fail the commit if the branch doesn't match `branch_pattern`
+	pub require_branch_match: Option<bool>,
+	/// named templates, `default` is used when `--template` isn't passed
+	pub templates: Option<HashMap<String, Template>>,
+	/// fills in the template and arguments for a commit convention and validates against it
+	pub preset: Option<Preset>,
+	pub changelog: Option<ChangelogOptions>,
+	pub release: Option<ReleaseOptions>,
+	/// people to credit with `--with`, alias to `Name <email>`
+	pub team: Option<HashMap<String, String>>,
+	pub trailers: Option<Vec<TrailerOptions>>,
+	/// configs loaded before this one, paths relative to it or names in `~/.config/glitter`
+	pub extends: Option<Vec<String>>,
 }
 // tests
 #[cfg(test)]
 mod tests {
 	use std::path::PathBuf;
 
-	use super::{commit_msg, Arguments, CommitMessageArguments, CustomTaskOptions, GlitterRc};
+	use super::{
+		commit_msg, Arguments, Case, CommitMessageArguments, CustomTaskOptions, GlitterRc,
+		Transform,
+	};
 
 	#[test]
 	fn check_commit_message() {
@@ -147,28 +373,32 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		let config = GlitterRc {
 			commit_message: "$1($2): $3+".to_string(),
-			arguments: None,
 			commit_message_arguments: Some(vec![CommitMessageArguments {
 				argument: 1,
-				case: Some("snake".to_string()),
+				case: Some(Case::Snake),
 				type_enums: Some(vec![
 					"fix".to_owned(),
 					"feat".to_owned(),
 					"chore".to_owned(),
 				]),
+				..Default::default()
 			}]),
-			fetch: None,
 			custom_tasks: Some(vec![CustomTaskOptions {
 				name: "fmt".to_owned(),
 				execute: Some(vec!["cargo fmt".to_owned()]),
 			}]),
-			__default: None,
-			hooks: None,
-			verbose: None,
+			..Default::default()
 		};
 
 		assert_eq!(commit_msg(), "$1+".to_string());
@@ -188,31 +418,96 @@ mod tests {
 				no_verify: Some(Some(false)),
 				verbose: Some(Some(false)),
 				no_add: Some(Some(false)),
+				interactive: Some(Some(false)),
+				template: None,
+				body: vec![],
+				breaking: Some(Some(false)),
+				format: None,
+				with: vec![],
+				trailer: vec![],
 			}
 		);
 		assert_eq!(
 			config,
 			GlitterRc {
 				commit_message: "$1($2): $3+".to_string(),
-				arguments: None,
 				commit_message_arguments: Some(vec![CommitMessageArguments {
 					argument: 1,
-					case: Some("snake".to_string()),
+					case: Some(Case::Snake),
 					type_enums: Some(vec![
 						"fix".to_owned(),
 						"feat".to_owned(),
 						"chore".to_owned()
-					])
+					]),
+					..Default::default()
 				}]),
-				fetch: None,
 				custom_tasks: Some(vec![CustomTaskOptions {
 					name: "fmt".to_owned(),
 					execute: Some(vec!["cargo fmt".to_owned()])
 				}]),
-				__default: None,
-				hooks: None,
-				verbose: None
+				..Default::default()
 			}
 		);
 	}
+
+	#[test]
+	fn transforms() {
+		let arg = serde_json::from_str::<CommitMessageArguments>(
+			r#"{
+				"argument": 3,
+				"case": "Screaming-Snake",
+				"transforms": ["trim", { "case": "foreign-key" }, { "truncate": 50 }, { "replace": { "pattern": "a", "with": "b" } }, "strip-emoji"]
+			}"#,
+		)
+		.unwrap();
+
+		assert_eq!(arg.case, Some(Case::ScreamingSnake));
+		assert_eq!(
+			arg.transforms,
+			Some(vec![
+				Transform::Trim,
+				Transform::Case(Case::ForeignKey),
+				Transform::Truncate(50),
+				Transform::Replace {
+					pattern: "a".to_owned(),
+					with: "b".to_owned()
+				},
+				Transform::StripEmoji
+			])
+		);
+
+		assert!(serde_json::from_str::<CommitMessageArguments>(
+			r#"{ "argument": 1, "case": "shouting" }"#
+		)
+		.is_err());
+		assert!(serde_json::from_str::<CommitMessageArguments>(
+			r#"{ "argument": 1, "transforms": ["uppercase"] }"#
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn with_stops_at_the_arguments() {
+		use structopt::StructOpt;
+
+		let args = Arguments::from_iter_safe(vec![
+			"glitter", "push", "--with", "alice", "feat", "add", "thing",
+		])
+		.unwrap();
+		assert_eq!(args.with, vec!["alice"]);
+		assert_eq!(args.arguments, vec!["feat", "add", "thing"]);
+
+		let args = Arguments::from_iter_safe(vec![
+			"glitter",
+			"push",
+			"-w",
+			"alice,bob",
+			"--with",
+			"cy",
+			"feat",
+		])
+		.unwrap();
+		assert_eq!(args.with, vec!["alice", "bob", "cy"]);
+		assert_eq!(args.arguments, vec!["feat"]);
+	}
 }
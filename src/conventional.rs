@@ -0,0 +1,233 @@
+use crate::config::{Case, CommitMessageArguments, GlitterRc, Preset};
+use colored::*;
+use fancy_regex::Regex;
+use std::io::Error;
+
+// `type(scope)!: description`, the scope and `!` are optional
+const SUBJECT: &str =
+	r"^(?P<type>[A-Za-z]+)(?:\((?P<scope>[^()\r\n]+)\))?(?P<breaking>!)?: (?P<description>\S.*)$";
+// `Token: value` or `Token #value`, tokens use `-` instead of spaces except for BREAKING CHANGE
+const FOOTER: &str = r"^(?:BREAKING CHANGE|[A-Za-z0-9-]+)(?:: | #)\S";
+
+pub const TEMPLATE: &str = "$type$[($scope)]${breaking:-}: $subject+";
+
+pub fn types() -> Vec<String> {
+	[
+		"feat", "fix", "build", "chore", "ci", "docs", "perf", "refactor", "revert", "style",
+		"test",
+	]
+	.iter()
+	.map(|t| t.to_string())
+	.collect()
+}
+
+// fill in the conventional template and `type`, `scope` and `subject` arguments,
+// keeping anything the glitterrc sets itself. `has_template` is whether it sets a commit_message
+pub fn apply_preset(mut config: GlitterRc, has_template: bool) -> GlitterRc {
+	if config.preset != Some(Preset::Conventional) {
+		return config;
+	}
+	if !has_template {
+		config.commit_message = TEMPLATE.to_owned();
+	}
+
+	let mut arguments = config.commit_message_arguments.take().unwrap_or_default();
+	let defaults = vec![
+		CommitMessageArguments {
+			argument: 1,
+			name: Some("type".to_owned()),
+			case: Some(Case::Lower),
+			type_enums: Some(types()),
+			..Default::default()
+		},
+		CommitMessageArguments {
+			argument: 2,
+			name: Some("scope".to_owned()),
+			..Default::default()
+		},
+		CommitMessageArguments {
+			argument: 3,
+			name: Some("subject".to_owned()),
+			no_trailing_period: Some(true),
+			..Default::default()
+		},
+	];
+	for default in defaults {
+		if !arguments.iter().any(|arg| arg.argument == default.argument) {
+			arguments.push(default);
+		}
+	}
+	arguments.sort_by_key(|arg| arg.argument);
+	config.commit_message_arguments = Some(arguments);
+	config
+}
+
+fn invalid(message: String) -> anyhow::Error {
+	anyhow::Error::new(Error::new(std::io::ErrorKind::InvalidInput, message))
+}
+
+// the part of a conventional subject after `type(scope): `
+pub fn description(subject: &str) -> Option<String> {
+	Regex::new(SUBJECT)
+		.ok()?
+		.captures(subject)
+		.ok()??
+		.name("description")
+		.map(|description| description.as_str().to_owned())
+}
+
+// add the `BREAKING CHANGE:` footer unless one was written by hand
+pub fn with_breaking_footer(subject: &str, mut paragraphs: Vec<String>) -> Vec<String> {
+	let has_footer = paragraphs.iter().any(|paragraph| {
+		paragraph.lines().any(|line| {
+			line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+		})
+	});
+	if !has_footer {
+		paragraphs = append_trailers(
+			paragraphs,
+			&[format!(
+				"BREAKING CHANGE: {}",
+				description(subject).unwrap_or_else(|| subject.to_owned())
+			)],
+		);
+	}
+	paragraphs
+}
+
+// add `Token: value` lines to the footer block, starting one if the message doesn't end with one.
+// lines that are already there aren't repeated
+pub fn append_trailers(mut paragraphs: Vec<String>, trailers: &[String]) -> Vec<String> {
+	for trailer in trailers {
+		match paragraphs.last_mut() {
+			Some(last) if is_footer_block(last) => {
+				if !last.lines().any(|line| line == trailer) {
+					last.push('\n');
+					last.push_str(trailer);
+				}
+			}
+			_ => paragraphs.push(trailer.clone()),
+		}
+	}
+	paragraphs
+}
+
+// whether a paragraph starts like `Token: value`
+pub fn is_footer_block(paragraph: &str) -> bool {
+	let footer = Regex::new(FOOTER).unwrap();
+	paragraph
+		.lines()
+		.next()
+		.is_some_and(|line| footer.is_match(line).unwrap_or(false))
+}
+
+// check a rendered message against the conventional commits spec
+pub fn validate(subject: &str, paragraphs: &[String]) -> anyhow::Result<()> {
+	if !Regex::new(SUBJECT)?.is_match(subject)? {
+		return Err(invalid(format!(
+			"`{}` is not a conventional commit. The subject must look like {}",
+			subject,
+			"type(scope)!: description".red()
+		)));
+	}
+
+	let footer = Regex::new(FOOTER)?;
+	for line in paragraphs.iter().flat_map(|paragraph| paragraph.lines()) {
+		let lower = line.to_lowercase();
+		if (lower.starts_with("breaking change") || lower.starts_with("breaking-change"))
+			&& !footer.is_match(line)?
+		{
+			return Err(invalid(format!(
+				"`{}` is not a valid footer. Breaking changes are written as {}",
+				line,
+				"BREAKING CHANGE: description".red()
+			)));
+		}
+	}
+	// the last paragraph is the footer block if it starts like a footer, then every line has to be one
+	if let Some(last) = paragraphs.last().filter(|last| is_footer_block(last)) {
+		for line in last.lines() {
+			// indented lines continue the footer above them
+			if !footer.is_match(line)? && !line.starts_with(' ') {
+				return Err(invalid(format!(
+					"`{}` is not a valid footer. Footers are written as {} or {}",
+					line,
+					"Token: value".red(),
+					"Token #value".red()
+				)));
+			}
+		}
+	}
+	Ok(())
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use crate::config::{Case, GlitterRc, Preset};
+
+	use super::{
+		append_trailers, apply_preset, description, validate, with_breaking_footer, TEMPLATE,
+	};
+
+	#[test]
+	fn preset_fills_template() {
+		let config = GlitterRc {
+			commit_message: "$1+".to_string(),
+			preset: Some(Preset::Conventional),
+			..Default::default()
+		};
+		// a commit_message set in the glitterrc is kept, even if it's the same as the default
+		assert_eq!(apply_preset(config.clone(), true).commit_message, "$1+");
+
+		let config = apply_preset(config, false);
+		assert_eq!(config.commit_message, TEMPLATE);
+		let arguments = config.commit_message_arguments.unwrap();
+		assert_eq!(arguments.len(), 3);
+		assert_eq!(arguments[0].case, Some(Case::Lower));
+		assert_eq!(arguments[2].name.as_deref(), Some("subject"));
+	}
+
+	#[test]
+	fn validates_the_spec() {
+		assert!(validate("feat(api)!: drop v1", &[]).is_ok());
+		assert!(validate("fix: typo", &["Refs #12\nReviewed-by: Z".to_owned()]).is_ok());
+		assert!(validate("feat(api) drop v1", &[]).is_err());
+		assert!(validate("feat(): x", &[]).is_err());
+		assert!(validate("fix: x", &["breaking change: oops".to_owned()]).is_err());
+		assert!(validate("fix: x", &["Refs: 1\nnot a footer".to_owned()]).is_err());
+	}
+
+	#[test]
+	fn breaking_footer() {
+		assert_eq!(description("feat(api)!: drop v1").unwrap(), "drop v1");
+		assert_eq!(
+			with_breaking_footer("feat(api)!: drop v1", vec!["Why.".to_owned()]),
+			vec!["Why.", "BREAKING CHANGE: drop v1"]
+		);
+		assert_eq!(
+			with_breaking_footer("feat!: x", vec!["BREAKING CHANGE: y".to_owned()]),
+			vec!["BREAKING CHANGE: y"]
+		);
+		assert_eq!(
+			with_breaking_footer("feat!: x", vec!["Refs: 12".to_owned()]),
+			vec!["Refs: 12\nBREAKING CHANGE: x"]
+		);
+	}
+
+	#[test]
+	fn trailers_join_the_footer() {
+		let trailers = vec!["Co-authored-by: A <a@b.c>".to_owned()];
+		assert_eq!(
+			append_trailers(vec!["Why.".to_owned()], &trailers),
+			vec!["Why.", "Co-authored-by: A <a@b.c>"]
+		);
+		assert_eq!(
+			append_trailers(
+				vec!["Refs: 12\nCo-authored-by: A <a@b.c>".to_owned()],
+				&trailers
+			),
+			vec!["Refs: 12\nCo-authored-by: A <a@b.c>"]
+		);
+	}
+}
@@ -0,0 +1,277 @@
+use crate::cli::git_output;
+use crate::config::GlitterRc;
+use crate::lint::{strip_comments, violations};
+use colored::*;
+use std::fs;
+use std::io::{BufRead, Error, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// `GLITTER_EDITMSG` in the git directory, which isn't `.git` in a worktree
+fn edit_file() -> PathBuf {
+	match git_output(&["rev-parse", "--git-path", "GLITTER_EDITMSG"]) {
+		path if path.is_empty() => PathBuf::from(".git/GLITTER_EDITMSG"),
+		path => PathBuf::from(path),
+	}
+}
+
+fn aborted() -> anyhow::Error {
+	anyhow::Error::new(Error::new(
+		std::io::ErrorKind::Interrupted,
+		"Aborted, nothing was committed.",
+	))
+}
+
+// the subject and paragraphs as git would write them
+fn join(subject: &str, paragraphs: &[String]) -> String {
+	let mut message = subject.to_owned();
+	for paragraph in paragraphs {
+		message.push_str("\n\n");
+		message.push_str(paragraph);
+	}
+	message
+}
+
+// what goes under the message in the editor: the template and what each argument accepts
+pub fn hints(config: &GlitterRc) -> String {
+	let mut hints = vec![
+		String::new(),
+		"# Edit the commit message above, lines starting with # are ignored.".to_owned(),
+		"# Save an empty message to abort.".to_owned(),
+		"#".to_owned(),
+		format!("# Template: {}", config.commit_message),
+	];
+	for arg in config.commit_message_arguments.iter().flatten() {
+		if let Some(ref type_enums) = arg.type_enums {
+			hints.push(format!("# {}: {}", arg.label(), type_enums.join(", ")));
+		}
+		if let Some(ref pattern) = arg.pattern {
+			hints.push(format!("# {} matches: {}", arg.label(), pattern));
+		}
+	}
+	hints.join("\n") + "\n"
+}
+
+// the edited file back into a subject and paragraphs, `None` if nothing is left
+pub fn read_message(edited: &str) -> Option<(String, Vec<String>)> {
+	let message = strip_comments(edited);
+	let mut paragraphs = message
+		.split("\n\n")
+		.map(|paragraph| paragraph.trim().to_owned())
+		.filter(|paragraph| !paragraph.is_empty());
+	let subject = paragraphs.next()?;
+	Some((subject, paragraphs.collect()))
+}
+
+// $GIT_EDITOR, then $EDITOR, then vi
+fn open_editor(path: &Path) -> anyhow::Result<()> {
+	let editor = ["GIT_EDITOR", "EDITOR"]
+		.iter()
+		.filter_map(|name| std::env::var(name).ok())
+		.find(|editor| !editor.trim().is_empty())
+		.unwrap_or_else(|| "vi".to_owned());
+	run_editor(&editor, path)
+}
+
+// run the editor through the shell like git does, so it may come with arguments or quotes,
+// eg. `code --wait`
+fn run_editor(editor: &str, path: &Path) -> anyhow::Result<()> {
+	let status = Command::new("sh")
+		.arg("-c")
+		.arg(format!("{} \"$1\"", editor))
+		.arg(editor)
+		.arg(path)
+		.status()
+		.map_err(|err| {
+			anyhow::Error::new(Error::new(
+				err.kind(),
+				format!("Couldn't open the editor `{}`: {}", editor, err),
+			))
+		})?;
+	if !status.success() {
+		return Err(anyhow::Error::new(Error::other(format!(
+			"The editor `{}` exited with {}",
+			editor, status
+		))));
+	}
+	Ok(())
+}
+
+// write the message to `GLITTER_EDITMSG` with hints, open the editor and read it back
+pub fn edit(config: &GlitterRc, subject: &str, paragraphs: &[String]) -> anyhow::Result<String> {
+	let edit_file = edit_file();
+	fs::write(
+		&edit_file,
+		format!("{}\n{}", join(subject, paragraphs), hints(config)),
+	)?;
+	open_editor(&edit_file)?;
+	let edited = fs::read_to_string(&edit_file)?;
+	fs::remove_file(&edit_file).ok();
+	Ok(edited)
+}
+
+// ask whether to commit the message, letting it be edited first. edited messages are checked
+// against the template again unless `validate` is false (raw mode)
+pub fn confirm(
+	config: &GlitterRc,
+	mut subject: String,
+	mut paragraphs: Vec<String>,
+	validate: bool,
+	input: &mut impl BufRead,
+	mut edit_message: impl FnMut(&str, &[String]) -> anyhow::Result<String>,
+) -> anyhow::Result<(String, Vec<String>)> {
+	let mut valid = true;
+	loop {
+		print!(
+			"{} ",
+			if valid {
+				"Commit? [Y/n/e]"
+			} else {
+				"Commit? [n/e]"
+			}
+			.bold()
+		);
+		std::io::stdout().flush()?;
+
+		let mut answer = String::new();
+		if input.read_line(&mut answer)? == 0 {
+			return Err(aborted());
+		}
+		match &*answer.trim().to_lowercase() {
+			"" | "y" | "yes" if valid => return Ok((subject, paragraphs)),
+			"n" | "no" => return Err(aborted()),
+			"e" | "edit" => {
+				let (edited_subject, edited_paragraphs) =
+					read_message(&edit_message(&subject, &paragraphs)?).ok_or_else(aborted)?;
+				subject = edited_subject;
+				paragraphs = edited_paragraphs;
+
+				println!(
+					"Commit message: {}",
+					format_args!(
+						"{}{}{}",
+						"`".green(),
+						subject.underline().green(),
+						"`".green()
+					)
+				);
+				for paragraph in &paragraphs {
+					println!("\n{}", paragraph.green());
+				}
+				if validate {
+					let found = violations(config, &join(&subject, &paragraphs))?;
+					for violation in &found {
+						println!("{} {}", "Error".red(), violation);
+					}
+					valid = found.is_empty();
+				}
+			}
+			_ if valid => println!("Answer y to commit, n to abort or e to edit the message."),
+			_ => println!(
+				"The message doesn't follow the template, answer e to edit it or n to abort."
+			),
+		}
+	}
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::config::{CommitMessageArguments, GlitterRc};
+
+	use super::{confirm, hints, read_message};
+
+	fn config() -> GlitterRc {
+		GlitterRc {
+			commit_message: "$1: $2+".to_string(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 1,
+				name: Some("type".to_owned()),
+				type_enums: Some(vec!["fix".to_owned(), "feat".to_owned()]),
+				..Default::default()
+			}]),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn reads_the_edited_message() {
+		let hints = hints(&config());
+		assert!(hints.contains("# Template: $1: $2+"));
+		assert!(hints.contains("# Argument 1 (type): fix, feat"));
+
+		let edited = format!(
+			"fix: typo\n\nA body\nover two lines.\n\n\nRefs: 12\n{}",
+			hints
+		);
+		assert_eq!(
+			read_message(&edited).unwrap(),
+			(
+				"fix: typo".to_owned(),
+				vec!["A body\nover two lines.".to_owned(), "Refs: 12".to_owned()]
+			)
+		);
+		assert!(read_message(&hints).is_none());
+	}
+
+	#[test]
+	fn confirms_and_revalidates() {
+		let subject = "fix: typo".to_owned();
+		let no_edit = |_: &str, _: &[String]| -> anyhow::Result<String> { unreachable!() };
+
+		let (confirmed, _) = confirm(
+			&config(),
+			subject.clone(),
+			vec![],
+			true,
+			&mut Cursor::new("\n"),
+			no_edit,
+		)
+		.unwrap();
+		assert_eq!(confirmed, subject);
+		assert!(confirm(
+			&config(),
+			subject.clone(),
+			vec![],
+			true,
+			&mut Cursor::new("n\n"),
+			no_edit
+		)
+		.is_err());
+
+		// the first edit breaks the template so `y` isn't accepted until it's fixed
+		let mut edits = vec!["feat: add editor\n\nWhy.", "chore: oops"];
+		let (subject, paragraphs) = confirm(
+			&config(),
+			subject,
+			vec![],
+			true,
+			&mut Cursor::new("e\ny\ne\ny\n"),
+			|_: &str, _: &[String]| Ok(edits.pop().unwrap().to_owned()),
+		)
+		.unwrap();
+		assert_eq!(subject, "feat: add editor");
+		assert_eq!(paragraphs, vec!["Why."]);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn runs_the_editor_through_the_shell() {
+		use super::run_editor;
+		use std::fs;
+
+		let dir = std::env::temp_dir().join(format!("glitter-editor-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("EDIT MSG");
+		fs::write(&path, "fix: typo\n").unwrap();
+
+		// arguments and quotes in the editor are kept, the path is passed as one argument
+		run_editor("printf '%s\\n' 'fix: a typo' >", &path).unwrap();
+		assert_eq!(fs::read_to_string(&path).unwrap(), "fix: a typo\n");
+		assert!(run_editor("false", &path).is_err());
+
+		fs::remove_dir_all(&dir).ok();
+	}
+}
@@ -1,48 +1,327 @@
 use crate::config::GlitterRc;
+use crate::conventional;
 use anyhow::Context;
-use std::fs::File;
-use std::path::Path;
-// parse the config file
-pub fn parse(path: &Path) -> anyhow::Result<GlitterRc> {
-	let does_exist = Path::new(path).exists();
-	if !does_exist {
-		Ok(GlitterRc {
-			fetch: None,
-			commit_message: "$1+".to_owned(),
-			arguments: None,
-			custom_tasks: None,
-			commit_message_arguments: None,
-			__default: Some(true),
-			hooks: None,
-			verbose: None,
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Format {
+	Json,
+	// json with comments, trailing commas and unquoted keys
+	Json5,
+	Toml,
+	Yaml,
+}
+
+impl fmt::Display for Format {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			Format::Json => "JSON",
+			Format::Json5 => "JSON5",
+			Format::Toml => "TOML",
+			Format::Yaml => "YAML",
+		})
+	}
+}
+
+// checked next to the glitterrc path when it doesn't exist itself, eg. `.glitterrc.toml`
+const EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json5"];
+
+// the format from the file extension, or from the content for files like `.glitterrc`
+pub fn detect(path: &Path, content: &str) -> Format {
+	match path.extension().and_then(|ext| ext.to_str()) {
+		Some("json") => return Format::Json,
+		Some("json5") => return Format::Json5,
+		Some("toml") => return Format::Toml,
+		Some("yaml") | Some("yml") => return Format::Yaml,
+		_ => {}
+	}
+	// only the first line that isn't a comment decides, later lines may be nested yaml values
+	// like `- make CC=clang`
+	let line = content
+		.lines()
+		.map(str::trim)
+		.find(|line| !line.is_empty() && !line.starts_with('#'))
+		.unwrap_or_default();
+	if line.starts_with('{') || line.starts_with("//") || line.starts_with("/*") {
+		// plain json is valid json5 too, it's parsed as json first for the clearer errors
+		return Format::Json;
+	}
+	// `[table]` or `key = value`, a yaml `key: value` has the `:` before any `=`
+	let key = line.split('=').next().unwrap_or_default().trim();
+	if line.starts_with('[')
+		|| (line.contains('=')
+			&& !key.is_empty()
+			&& key
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || "_-.\"' ".contains(c)))
+	{
+		return Format::Toml;
+	}
+	Format::Yaml
+}
+
+// the file as a json value, so every format can be merged the same way
+pub fn to_value(content: &str, format: Format) -> anyhow::Result<Value> {
+	Ok(match format {
+		Format::Json => match serde_json::from_str(content) {
+			Ok(value) => value,
+			// comments or trailing commas in a `.glitterrc`
+			Err(err) => json5::from_str(content).map_err(|_| err)?,
+		},
+		Format::Json5 => json5::from_str(content)?,
+		Format::Toml => toml::from_str(content)?,
+		Format::Yaml => serde_yaml::from_str(content)?,
+	})
+}
+
+pub fn from_str(content: &str, format: Format) -> anyhow::Result<GlitterRc> {
+	Ok(serde_json::from_value(to_value(content, format)?)?)
+}
+
+// the glitterrc itself, or `<glitterrc>.toml` / `.yaml` / `.yml` / `.json5` next to it
+fn find(path: &Path) -> Option<PathBuf> {
+	if path.exists() {
+		return Some(path.to_path_buf());
+	}
+	EXTENSIONS
+		.iter()
+		.map(|ext| {
+			let mut candidate = path.as_os_str().to_owned();
+			candidate.push(format!(".{}", ext));
+			PathBuf::from(candidate)
 		})
-	} else {
-		let file = File::open(path)?;
-		match serde_json::from_reader(file) {
-			Ok(json) => Ok(json),
-			Err(err) => Err(anyhow::Error::new(err)).with_context(|| "error parsing glitterrc"),
+		.find(|candidate| candidate.exists())
+}
+
+// look for the glitterrc in `cwd` and its parents, stopping at the repository root
+fn find_upwards(rc_path: &Path, cwd: &Path) -> Option<PathBuf> {
+	if rc_path.is_absolute() {
+		return find(rc_path);
+	}
+	for dir in cwd.ancestors() {
+		if let Some(found) = find(&dir.join(rc_path)) {
+			return Some(found);
+		}
+		if dir.join(".git").exists() {
+			break;
 		}
 	}
+	None
+}
+
+// `$XDG_CONFIG_HOME/glitter`, or `~/.config/glitter`
+pub fn config_dir() -> Option<PathBuf> {
+	std::env::var_os("XDG_CONFIG_HOME")
+		.filter(|dir| !dir.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+		.map(|dir| dir.join("glitter"))
+}
+
+// the files that make up the config, from the lowest to the highest precedence:
+// the global config, the glitterrc and the `.local` glitterrc next to it
+pub fn layers(rc_path: &Path, cwd: &Path, config_dir: Option<&Path>) -> Vec<PathBuf> {
+	let mut layers = Vec::new();
+	if let Some(global) = config_dir.and_then(|dir| find(&dir.join("config"))) {
+		layers.push(global);
+	}
+	let mut local_name = rc_path.file_name().unwrap_or_default().to_owned();
+	local_name.push(".local");
+	match find_upwards(rc_path, cwd) {
+		Some(project) => {
+			let local = find(&project.with_file_name(&local_name));
+			layers.push(project);
+			layers.extend(local);
+		}
+		None => layers.extend(find_upwards(&rc_path.with_file_name(&local_name), cwd)),
+	}
+	layers
+}
+
+// layer `overlay` on top of `base`: maps merge key by key, anything else is replaced.
+// a key ending in `+`, eg. `"hooks+"`, appends to the list instead of replacing it
+pub fn merge(base: &mut Value, overlay: Value) -> anyhow::Result<()> {
+	let overlay = match overlay {
+		Value::Object(overlay) => overlay,
+		overlay => {
+			*base = overlay;
+			return Ok(());
+		}
+	};
+	if !base.is_object() {
+		*base = Value::Object(Map::new());
+	}
+	let base = base.as_object_mut().unwrap();
+	for (key, value) in overlay {
+		match key.strip_suffix('+') {
+			Some(key) => match (base.entry(key).or_insert(Value::Array(Vec::new())), value) {
+				(Value::Array(list), Value::Array(items)) => list.extend(items),
+				_ => {
+					return Err(anyhow::Error::new(Error::new(
+						std::io::ErrorKind::InvalidInput,
+						format!("`{}+` can only append a list to a list", key),
+					)))
+				}
+			},
+			None => merge(base.entry(key).or_insert(Value::Null), value)?,
+		}
+	}
+	Ok(())
+}
+
+// an `extends` entry, next to the config that extends it or in the installed config directory
+pub(crate) fn resolve(
+	name: &str,
+	from: &Path,
+	config_dir: Option<&Path>,
+) -> anyhow::Result<PathBuf> {
+	let dir = from.parent().unwrap_or_else(|| Path::new(""));
+	find(&dir.join(name))
+		.or_else(|| config_dir.and_then(|config_dir| find(&config_dir.join(name))))
+		.ok_or_else(|| {
+			anyhow::Error::new(Error::new(
+				std::io::ErrorKind::NotFound,
+				format!(
+					"{} extends `{}`, which isn't in {}{}",
+					from.display(),
+					name,
+					dir.display(),
+					config_dir
+						.map(|config_dir| format!(" or {}", config_dir.display()))
+						.unwrap_or_default()
+				),
+			))
+		})
+}
+
+pub(crate) fn extends(value: &mut Value, path: &Path) -> anyhow::Result<Vec<String>> {
+	let extends = value
+		.as_object_mut()
+		.and_then(|value| value.remove("extends"));
+	match extends {
+		None | Some(Value::Null) => Ok(Vec::new()),
+		Some(Value::String(name)) => Ok(vec![name]),
+		Some(Value::Array(names)) if names.iter().all(Value::is_string) => Ok(names
+			.into_iter()
+			.filter_map(|name| name.as_str().map(str::to_owned))
+			.collect()),
+		Some(_) => Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"`extends` in {} should be a path or a list of paths",
+				path.display()
+			),
+		))),
+	}
+}
+
+// a config file with the configs it extends merged underneath it, in order.
+// `chain` holds the files currently being loaded, to catch configs extending each other
+pub fn load(
+	path: &Path,
+	config_dir: Option<&Path>,
+	chain: &mut Vec<PathBuf>,
+) -> anyhow::Result<Value> {
+	let canonical = path.canonicalize()?;
+	if chain.contains(&canonical) {
+		return Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"The glitterrc extends itself: {} -> {}",
+				chain
+					.iter()
+					.map(|path| path.display().to_string())
+					.collect::<Vec<String>>()
+					.join(" -> "),
+				canonical.display()
+			),
+		)));
+	}
+	let content = fs::read_to_string(path)?;
+	let format = detect(path, &content);
+	let mut value = to_value(&content, format)
+		.with_context(|| format!("error parsing glitterrc {} as {}", path.display(), format))?;
+
+	let mut base = Value::Object(Map::new());
+	chain.push(canonical);
+	for name in extends(&mut value, path)? {
+		let extended = load(&resolve(&name, path, config_dir)?, config_dir, chain)?;
+		merge(&mut base, extended)?;
+	}
+	chain.pop();
+	merge(&mut base, value)
+		.with_context(|| format!("error merging glitterrc {}", path.display()))?;
+	Ok(base)
+}
+
+// parse the config file, layered on top of the global config in `config_dir`
+pub fn parse(path: &Path, config_dir: Option<&Path>) -> anyhow::Result<GlitterRc> {
+	let layers = layers(path, &std::env::current_dir()?, config_dir);
+	if layers.is_empty() {
+		return Ok(default());
+	}
+	let mut value = Value::Object(Map::new());
+	for layer in &layers {
+		let overlay = load(layer, config_dir, &mut Vec::new())?;
+		merge(&mut value, overlay)
+			.with_context(|| format!("error merging glitterrc {}", layer.display()))?;
+	}
+	let has_template = value.get("commit_message").is_some();
+	match serde_json::from_value(value) {
+		Ok(config) => Ok(conventional::apply_preset(config, has_template)),
+		Err(err) => Err(anyhow::Error::new(err)).with_context(|| {
+			format!(
+				"error parsing glitterrc {}",
+				layers
+					.iter()
+					.map(|layer| layer.display().to_string())
+					.collect::<Vec<String>>()
+					.join(" + ")
+			)
+		}),
+	}
+}
+
+// `parse` with the user's global config
+pub fn parse_global(path: &Path) -> anyhow::Result<GlitterRc> {
+	parse(path, config_dir().as_deref())
+}
+
+// used when there's no glitterrc at all
+pub fn default() -> GlitterRc {
+	GlitterRc {
+		commit_message: "$1+".to_owned(),
+		__default: Some(true),
+		..Default::default()
+	}
 }
 // tests
 #[cfg(test)]
 mod tests {
 	use std::path::PathBuf;
 
-	use crate::config::{CommitMessageArguments, CustomTaskOptions, GlitterRc};
+	use std::fs;
+	use std::path::Path;
+
+	use crate::config::{Case, CommitMessageArguments, CustomTaskOptions, GlitterRc, Transform};
+
+	use serde_json::json;
 
-	use super::parse;
+	use super::{detect, from_str, layers, load, merge, parse, to_value, Format};
 
 	#[test]
 	fn parse_correctly() {
 		assert_eq!(
-			parse(&PathBuf::from(".glitterrc")).unwrap(),
+			parse(&PathBuf::from(".glitterrc"), None).unwrap(),
 			GlitterRc {
 				commit_message: "$1: $2: $3+".to_string(),
-				arguments: None,
 				commit_message_arguments: Some(vec![CommitMessageArguments {
 					argument: 1,
-					case: Some("lower".to_string()),
+					case: Some(Case::Lower),
 					type_enums: Some(vec![
 						"fix".to_string(),
 						"feat".to_string(),
@@ -52,9 +331,9 @@ mod tests {
 						"void".to_string(),
 						"deps".to_string(),
 						"ci".to_string()
-					])
+					]),
+					..Default::default()
 				}]),
-				fetch: None,
 				custom_tasks: Some(vec![
 					CustomTaskOptions {
 						name: String::from("fmt"),
@@ -65,16 +344,15 @@ mod tests {
 						execute: Some(vec![String::from("cargo lint")])
 					}
 				]),
-				__default: None,
 				hooks: Some(vec![String::from("fmt"), String::from("lint")]),
-				verbose: None
+				..Default::default()
 			}
 		)
 	}
 
 	#[test]
 	fn non_existant_file() {
-		assert!(parse(&PathBuf::from(".glitter"))
+		assert!(parse(&PathBuf::from(".glitter"), None)
 			.unwrap()
 			.__default
 			.is_some())
@@ -82,6 +360,211 @@ mod tests {
 
 	#[test]
 	fn broken_glitterrc() {
-		assert!(parse(&PathBuf::from(".glitterrc.broken")).is_err())
+		assert!(parse(&PathBuf::from(".glitterrc.broken"), None).is_err())
+	}
+
+	#[test]
+	fn detects_the_format() {
+		let path = Path::new(".glitterrc");
+		assert_eq!(detect(Path::new("a.toml"), "{}"), Format::Toml);
+		assert_eq!(detect(Path::new("a.yml"), ""), Format::Yaml);
+		assert_eq!(detect(Path::new("a.json5"), ""), Format::Json5);
+		assert_eq!(
+			detect(path, "  {\n\"commit_message\": \"$1+\"}"),
+			Format::Json
+		);
+		assert_eq!(detect(path, "// a comment\n{}"), Format::Json);
+		assert_eq!(
+			detect(path, "# a comment\ncommit_message = \"$1+\""),
+			Format::Toml
+		);
+		assert_eq!(
+			detect(path, "[[custom_tasks]]\nname = \"fmt\""),
+			Format::Toml
+		);
+		assert_eq!(
+			detect(path, "# a comment\ncommit_message: \"$1 = $2\""),
+			Format::Yaml
+		);
+		let yaml = "custom_tasks:\n  - name: build\n    execute:\n      - make CC=clang\n";
+		assert_eq!(detect(path, yaml), Format::Yaml);
+		assert!(to_value(yaml, detect(path, yaml)).is_ok());
+	}
+
+	#[test]
+	fn parses_every_format() {
+		let toml = r#"
+# every argument is trimmed and cut at 50 characters
+commit_message = "$1: $2+"
+hooks = ["fmt"]
+
+[[commit_message_arguments]]
+argument = 2
+case = "lower"
+transforms = ["trim", { truncate = 50 }]
+
+[[custom_tasks]]
+name = "fmt"
+execute = ["cargo fmt"]
+"#;
+		let yaml = r#"
+# every argument is trimmed and cut at 50 characters
+commit_message: "$1: $2+"
+hooks: [fmt]
+commit_message_arguments:
+  - argument: 2
+    case: lower
+    transforms: [trim, { truncate: 50 }]
+custom_tasks:
+  - name: fmt
+    execute: [cargo fmt]
+"#;
+		let json5 = r#"{
+	// every argument is trimmed and cut at 50 characters
+	commit_message: "$1: $2+",
+	hooks: ["fmt"],
+	commit_message_arguments: [
+		{ argument: 2, case: "lower", transforms: ["trim", { truncate: 50 }] },
+	],
+	custom_tasks: [{ name: "fmt", execute: ["cargo fmt"] }],
+}"#;
+		let toml = from_str(toml, Format::Toml).unwrap();
+		assert_eq!(toml.commit_message, "$1: $2+");
+		assert_eq!(toml.hooks, Some(vec!["fmt".to_owned()]));
+		assert_eq!(
+			toml.commit_message_arguments.as_ref().unwrap()[0].transforms,
+			Some(vec![Transform::Trim, Transform::Truncate(50)])
+		);
+		assert_eq!(
+			toml.commit_message_arguments.as_ref().unwrap()[0].case,
+			Some(Case::Lower)
+		);
+		assert_eq!(from_str(yaml, Format::Yaml).unwrap(), toml);
+		assert_eq!(from_str(json5, Format::Json5).unwrap(), toml);
+		// `.glitterrc` files sniffed as json may still have comments
+		assert_eq!(from_str(json5, Format::Json).unwrap(), toml);
+	}
+
+	#[test]
+	fn finds_glitterrc_with_an_extension() {
+		let dir = std::env::temp_dir().join(format!("glitter-formats-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(dir.join(".glitterrc.yaml"), "commit_message: \"$1: $2+\"\n").unwrap();
+
+		let config = parse(&dir.join(".glitterrc"), None).unwrap();
+		assert_eq!(config.commit_message, "$1: $2+");
+		assert!(config.__default.is_none());
+
+		fs::write(dir.join(".glitterrc.toml"), "commit_message = [").unwrap();
+		let err = parse(&dir.join(".glitterrc"), None).unwrap_err();
+		assert!(format!("{}", err).contains("as TOML"));
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn merges_layers() {
+		let mut config = json!({
+			"commit_message": "$1: $2+",
+			"hooks": ["fmt"],
+			"team": { "al": "Al <al@example.com>" },
+			"verbose": true
+		});
+		merge(
+			&mut config,
+			json!({
+				"hooks+": ["lint"],
+				"team": { "bo": "Bo <bo@example.com>" },
+				"custom_tasks+": [{ "name": "lint" }],
+				"verbose": null
+			}),
+		)
+		.unwrap();
+		assert_eq!(
+			config,
+			json!({
+				"commit_message": "$1: $2+",
+				"hooks": ["fmt", "lint"],
+				"team": { "al": "Al <al@example.com>", "bo": "Bo <bo@example.com>" },
+				"custom_tasks": [{ "name": "lint" }],
+				"verbose": null
+			})
+		);
+		merge(&mut config, json!({ "hooks": ["test"] })).unwrap();
+		assert_eq!(config["hooks"], json!(["test"]));
+		assert!(merge(&mut config, json!({ "team+": ["x"] })).is_err());
+	}
+
+	#[test]
+	fn discovers_layers() {
+		let dir = std::env::temp_dir().join(format!("glitter-layers-{}", std::process::id()));
+		let global = dir.join("home/glitter");
+		let repo = dir.join("repo");
+		let nested = repo.join("src/deep");
+		fs::create_dir_all(&global).unwrap();
+		fs::create_dir_all(repo.join(".git")).unwrap();
+		fs::create_dir_all(&nested).unwrap();
+		let rc_path = Path::new(".glitterrc");
+
+		assert!(layers(rc_path, &nested, Some(&global)).is_empty());
+
+		fs::write(global.join("config.yaml"), "verbose: true\n").unwrap();
+		fs::write(repo.join(".glitterrc.toml"), "hooks = [\"fmt\"]\n").unwrap();
+		fs::write(repo.join(".glitterrc.local"), "{ \"hooks+\": [\"lint\"] }").unwrap();
+		assert_eq!(
+			layers(rc_path, &nested, Some(&global)),
+			vec![
+				global.join("config.yaml"),
+				repo.join(".glitterrc.toml"),
+				repo.join(".glitterrc.local")
+			]
+		);
+		// the search stops at the repository root
+		fs::write(dir.join(".glitterrc"), "{}").unwrap();
+		fs::remove_file(repo.join(".glitterrc.toml")).unwrap();
+		assert_eq!(
+			layers(rc_path, &nested, None),
+			vec![repo.join(".glitterrc.local")]
+		);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn extends_other_configs() {
+		let dir = std::env::temp_dir().join(format!("glitter-extends-{}", std::process::id()));
+		let installed = dir.join("installed");
+		fs::create_dir_all(dir.join("shared")).unwrap();
+		fs::create_dir_all(&installed).unwrap();
+		fs::write(
+			installed.join("org.toml"),
+			"commit_message = \"$1: $2+\"\nhooks = [\"fmt\"]\n",
+		)
+		.unwrap();
+		fs::write(
+			dir.join("shared/rust.yaml"),
+			"extends: org\nhooks+: [clippy]\nverbose: true\n",
+		)
+		.unwrap();
+		fs::write(
+			dir.join(".glitterrc"),
+			"{ \"extends\": [\"shared/rust.yaml\"], \"verbose\": false }",
+		)
+		.unwrap();
+
+		let value = load(&dir.join(".glitterrc"), Some(&installed), &mut Vec::new()).unwrap();
+		assert_eq!(
+			value,
+			json!({ "commit_message": "$1: $2+", "hooks": ["fmt", "clippy"], "verbose": false })
+		);
+
+		let err = load(&dir.join(".glitterrc"), None, &mut Vec::new()).unwrap_err();
+		assert!(format!("{}", err).contains("extends `org`"));
+
+		fs::write(installed.join("org.toml"), "extends = \"../.glitterrc\"\n").unwrap();
+		let err = load(&dir.join(".glitterrc"), Some(&installed), &mut Vec::new()).unwrap_err();
+		assert!(format!("{}", err).starts_with("The glitterrc extends itself"));
+
+		fs::remove_dir_all(&dir).unwrap();
 	}
 }
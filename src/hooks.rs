@@ -0,0 +1,290 @@
+use crate::cli::{git_output, run_hooks, select_template};
+use crate::config::{Arguments, GlitterRc};
+use crate::lint::{range_messages, report, strip_comments};
+use colored::*;
+use std::fs;
+use std::io::{stdin, BufRead, Error};
+use std::path::{Path, PathBuf};
+
+// set while glitter itself runs `git commit`: the hooks tasks have already run, and unless
+// it's a raw commit the message has already been checked
+pub const TASKS_DONE: &str = "GLITTER_TASKS_DONE";
+pub const VALIDATED: &str = "GLITTER_VALIDATED";
+
+const MARKER: &str = "# installed by glitter";
+const GIT_HOOKS: [&str; 3] = ["commit-msg", "pre-commit", "pre-push"];
+
+// runs the hook that was there before glitter first, so husky and friends keep working
+fn shim(name: &str, rc_path: &Path) -> String {
+	// pre-push gets the refs on stdin, both hooks need to read them
+	let (save, feed) = if name == "pre-push" {
+		("input=$(cat)\n", "printf '%s\\n' \"$input\" | ")
+	} else {
+		("", "")
+	};
+	format!(
+		"#!/bin/sh\n{}, remove it with `glitter hooks uninstall`\n{}if [ -x \"$0.glitter-backup\" ]; then\n\t{}\"$0.glitter-backup\" \"$@\" || exit $?\nfi\n{}exec glitter --rc-path \"{}\" hooks run {} \"$@\"\n",
+		MARKER,
+		save,
+		feed,
+		feed,
+		rc_path.display(),
+		name
+	)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+	let mut backup = path.as_os_str().to_owned();
+	backup.push(".glitter-backup");
+	PathBuf::from(backup)
+}
+
+fn is_shim(path: &Path) -> bool {
+	fs::read_to_string(path).is_ok_and(|content| content.contains(MARKER))
+}
+
+// where git looks for hooks, respecting `core.hooksPath`
+fn hooks_dir() -> anyhow::Result<PathBuf> {
+	let dir = git_output(&["rev-parse", "--git-path", "hooks"]);
+	if dir.is_empty() {
+		return Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!("{} This is not a git repository.", "Fatal".red()),
+		)));
+	}
+	Ok(PathBuf::from(dir))
+}
+
+// write the shims, moving hooks that were already there to `<hook>.glitter-backup`
+pub fn install(dir: &Path, rc_path: &Path) -> anyhow::Result<()> {
+	fs::create_dir_all(dir)?;
+	for name in GIT_HOOKS {
+		let path = dir.join(name);
+		let backup = backup_path(&path);
+		if path.exists() && !is_shim(&path) {
+			if backup.exists() {
+				return Err(anyhow::Error::new(Error::new(
+					std::io::ErrorKind::AlreadyExists,
+					format!(
+						"{} Both {} and {} exist, remove one of them first.",
+						"Fatal".red(),
+						path.display(),
+						backup.display()
+					),
+				)));
+			}
+			fs::rename(&path, &backup)?;
+			println!(
+				"Moved the existing {} hook to {}",
+				name,
+				backup.display().to_string().underline()
+			);
+		}
+		fs::write(&path, shim(name, rc_path))?;
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+		}
+		println!("{} {}", "Installed".green(), path.display());
+	}
+	Ok(())
+}
+
+// remove the shims and put back whatever was there before
+pub fn uninstall(dir: &Path) -> anyhow::Result<()> {
+	for name in GIT_HOOKS {
+		let path = dir.join(name);
+		let backup = backup_path(&path);
+		if is_shim(&path) {
+			fs::remove_file(&path)?;
+			println!("{} {}", "Removed".green(), path.display());
+		}
+		if backup.exists() && !path.exists() {
+			fs::rename(&backup, &path)?;
+			println!("Restored the previous {} hook", name);
+		}
+	}
+	Ok(())
+}
+
+// the commits a push is about to send, from the `<local ref> <local sha> <remote ref> <remote sha>`
+// lines git writes to the pre-push hook
+fn pushed_messages(input: &mut impl BufRead) -> anyhow::Result<Vec<(String, String)>> {
+	let mut messages = Vec::new();
+	for line in input.lines() {
+		let line = line?;
+		let parts = line.split_whitespace().collect::<Vec<&str>>();
+		if parts.len() != 4 || parts[1].chars().all(|c| c == '0') {
+			// deleting a branch doesn't push any commits
+			continue;
+		}
+		let (local, remote) = (parts[1], parts[3]);
+		messages.extend(if remote.chars().all(|c| c == '0') {
+			// a new branch, everything the remote doesn't have yet
+			range_messages(&[local, "--not", "--remotes"])?
+		} else {
+			range_messages(&[&format!("{}..{}", remote, local)])?
+		});
+	}
+	Ok(messages)
+}
+
+pub fn run(
+	config: GlitterRc,
+	name: &str,
+	hook_args: &[String],
+	dry: bool,
+	verbose: bool,
+) -> anyhow::Result<()> {
+	let done = match name {
+		"commit-msg" => Some(VALIDATED),
+		"pre-commit" => Some(TASKS_DONE),
+		_ => None,
+	};
+	if done.is_some_and(|var| std::env::var_os(var).is_some()) {
+		return Ok(());
+	}
+	let config = select_template(config, None)?;
+	match name {
+		"commit-msg" => {
+			let file = hook_args.first().ok_or_else(|| {
+				anyhow::Error::new(Error::new(
+					std::io::ErrorKind::InvalidInput,
+					"The commit-msg hook needs the path of the message file.",
+				))
+			})?;
+			// merge commits are skipped like in `glitter lint`
+			if !git_output(&["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_empty() {
+				return Ok(());
+			}
+			let message = strip_comments(&fs::read_to_string(file)?);
+			report(&config, &[("commit-msg".to_owned(), message)])
+		}
+		"pre-commit" => {
+			run_hooks(&config, dry, verbose);
+			Ok(())
+		}
+		"pre-push" => report(&config, &pushed_messages(&mut stdin().lock())?),
+		_ => Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"There is no `{}` hook. Glitter handles {}",
+				name,
+				GIT_HOOKS.join(", ").red()
+			),
+		))),
+	}
+}
+
+pub fn hooks(config: GlitterRc, args: Arguments, dry: bool, verbose: bool) -> anyhow::Result<()> {
+	match args.arguments.first().map(String::as_str) {
+		Some("install") => install(&hooks_dir()?, &args.rc_path),
+		Some("uninstall") => uninstall(&hooks_dir()?),
+		Some("run") => match args.arguments.get(1) {
+			Some(name) => run(config, name, &args.arguments[2..], dry, verbose),
+			None => Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"Try `hooks run <hook>`, eg. `hooks run pre-commit`.",
+			))),
+		},
+		_ => Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			"Try `hooks install`, `hooks uninstall` or `hooks run <hook>`.",
+		))),
+	}
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::path::Path;
+
+	use super::{backup_path, install, is_shim, uninstall};
+
+	#[test]
+	fn installs_and_restores_hooks() {
+		let dir = std::env::temp_dir().join(format!("glitter-hooks-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(dir.join("pre-commit"), "#!/bin/sh\necho mine\n").unwrap();
+
+		install(&dir, Path::new(".glitterrc")).unwrap();
+		assert!(is_shim(&dir.join("commit-msg")));
+		assert!(is_shim(&dir.join("pre-commit")));
+		assert!(fs::read_to_string(dir.join("pre-push"))
+			.unwrap()
+			.contains("hooks run pre-push"));
+		assert!(backup_path(&dir.join("pre-commit")).exists());
+
+		// the shim runs the previous hook before glitter, and stops when it fails
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			use std::process::Command;
+
+			let bin = dir.join("bin");
+			fs::create_dir_all(&bin).unwrap();
+			fs::write(
+				bin.join("glitter"),
+				format!("#!/bin/sh\necho glitter >> {}/ran\n", dir.display()),
+			)
+			.unwrap();
+			fs::set_permissions(bin.join("glitter"), fs::Permissions::from_mode(0o755)).unwrap();
+			let run = |previous: &str| {
+				fs::write(
+					backup_path(&dir.join("pre-commit")),
+					format!(
+						"#!/bin/sh\necho previous >> {}/ran\n{}\n",
+						dir.display(),
+						previous
+					),
+				)
+				.unwrap();
+				fs::set_permissions(
+					backup_path(&dir.join("pre-commit")),
+					fs::Permissions::from_mode(0o755),
+				)
+				.unwrap();
+				fs::remove_file(dir.join("ran")).ok();
+				let status = Command::new(dir.join("pre-commit"))
+					.env(
+						"PATH",
+						format!("{}:{}", bin.display(), std::env::var("PATH").unwrap()),
+					)
+					.status()
+					.unwrap();
+				(
+					status.success(),
+					fs::read_to_string(dir.join("ran")).unwrap(),
+				)
+			};
+			assert_eq!(run("true"), (true, "previous\nglitter\n".to_owned()));
+			assert_eq!(run("exit 3"), (false, "previous\n".to_owned()));
+			fs::remove_dir_all(&bin).unwrap();
+			fs::remove_file(dir.join("ran")).unwrap();
+			fs::write(
+				backup_path(&dir.join("pre-commit")),
+				"#!/bin/sh\necho mine\n",
+			)
+			.unwrap();
+		}
+
+		// installing again leaves the backup alone
+		install(&dir, Path::new(".glitterrc")).unwrap();
+		assert_eq!(
+			fs::read_to_string(backup_path(&dir.join("pre-commit"))).unwrap(),
+			"#!/bin/sh\necho mine\n"
+		);
+
+		uninstall(&dir).unwrap();
+		assert!(!dir.join("commit-msg").exists());
+		assert_eq!(
+			fs::read_to_string(dir.join("pre-commit")).unwrap(),
+			"#!/bin/sh\necho mine\n"
+		);
+		assert!(!backup_path(&dir.join("pre-commit")).exists());
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
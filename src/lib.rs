@@ -1,12 +1,35 @@
+pub mod changelog;
+pub mod check;
 pub mod cli;
 pub mod config;
+pub mod conventional;
+pub mod editor;
 pub mod get_and_parse;
-use crate::cli::match_cmds;
+pub mod hooks;
+pub mod lint;
+pub mod release;
+pub mod template;
+pub mod wizard;
+use crate::cli::{git_output, match_cmds};
 use config::Arguments;
+use std::path::Path;
 
 // this function will parse configuration from the get_and_parse file and pass it onto the cli
-pub fn run(args: Arguments) -> anyhow::Result<()> {
-	let config = get_and_parse::parse(&args.rc_path)?;
+pub fn run(mut args: Arguments) -> anyhow::Result<()> {
+	let config = match get_and_parse::parse_global(&args.rc_path) {
+		Ok(config) => config,
+		// `config check` explains what's wrong with the glitterrc itself
+		Err(_) if args.action.eq_ignore_ascii_case("config") => get_and_parse::default(),
+		Err(err) => return Err(err),
+	};
+	// commands, tasks and `git add .` run from the repository root, like they would without a subdirectory
+	let root = git_output(&["rev-parse", "--show-toplevel"]);
+	if !root.is_empty() && std::env::current_dir()? != Path::new(&root) {
+		if args.rc_path.exists() {
+			args.rc_path = args.rc_path.canonicalize()?;
+		}
+		std::env::set_current_dir(&root)?;
+	}
 	match_cmds(args, config)?;
 
 	Ok(())
@@ -34,6 +57,13 @@ mod tests {
 			no_verify: Some(Some(false)),
 			verbose: Some(Some(false)),
 			no_add: Some(Some(false)),
+			interactive: Some(Some(false)),
+			template: None,
+			body: vec![],
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
 		};
 
 		run(args).unwrap();
@@ -0,0 +1,382 @@
+use crate::cli::{
+	apply_template, argument_index, convert_case, missing_trailers, select_template,
+	validate_argument,
+};
+use crate::config::{Arguments, GlitterRc, Preset};
+use crate::conventional;
+use crate::template::{tokenize, Key, Token};
+use colored::*;
+use fancy_regex::{escape, Regex};
+use std::io::{stdin, Error, IsTerminal, Read};
+use std::process::Command;
+
+// `(argument, value)` pairs read out of a subject, in the order the template uses them
+pub(crate) type Values = Vec<(usize, String)>;
+
+// turn template tokens back into a regex. every argument becomes a capture group and `groups`
+// records which argument each group belongs to, variables match anything
+fn pattern(config: &GlitterRc, tokens: &[Token], groups: &mut Vec<usize>) -> String {
+	let mut result = String::new();
+	for token in tokens {
+		match token {
+			Token::Literal(text) => result.push_str(&escape(text)),
+			Token::Group(group) => {
+				result.push_str(&format!("(?:{})?", pattern(config, group, groups)))
+			}
+			Token::Argument { key, .. } => match argument_index(config, key) {
+				Some(idx) => {
+					groups.push(idx);
+					result.push_str("(.+?)");
+				}
+				// `--breaking` only ever adds a `!`
+				None if *key == Key::Name("breaking".to_owned()) => result.push_str("!?"),
+				None => result.push_str(".*?"),
+			},
+		}
+	}
+	result
+}
+
+// the arguments a template uses as `$N+`, their value is the joined rest of the subject
+fn rest_arguments(config: &GlitterRc, tokens: &[Token], rest: &mut Vec<usize>) {
+	for token in tokens {
+		match token {
+			Token::Group(group) => rest_arguments(config, group, rest),
+			Token::Argument {
+				key, rest: true, ..
+			} => rest.extend(argument_index(config, key)),
+			_ => {}
+		}
+	}
+}
+
+// the values of each argument in `subject`, if it matches the commit message template at all
+fn parse_subject(config: &GlitterRc, subject: &str) -> anyhow::Result<Option<Values>> {
+	let mut groups = Vec::new();
+	let regex = Regex::new(&format!(
+		"^{}$",
+		pattern(config, &tokenize(&config.commit_message)?, &mut groups)
+	))?;
+	Ok(regex.captures(subject)?.map(|captures| {
+		groups
+			.iter()
+			.enumerate()
+			.filter_map(|(i, idx)| Some((*idx, captures.get(i + 1)?.as_str().to_owned())))
+			.collect()
+	}))
+}
+
+// the configs a subject may have been written with: every type template, then the template itself
+fn candidates(config: &GlitterRc) -> Vec<(GlitterRc, Option<(usize, String)>)> {
+	let mut candidates = Vec::new();
+	let typed = config
+		.commit_message_arguments
+		.iter()
+		.flatten()
+		.find(|arg| arg.type_templates.is_some() && arg.argument > 0);
+	if let Some(arg) = typed {
+		let mut templates = arg
+			.type_templates
+			.clone()
+			.unwrap_or_default()
+			.into_iter()
+			.collect::<Vec<_>>();
+		templates.sort_by(|a, b| a.0.cmp(&b.0));
+		for (value, template) in templates {
+			let mut typed_config = config.clone();
+			apply_template(&mut typed_config, &template);
+			candidates.push((typed_config, Some((arg.argument as usize, value))));
+		}
+	}
+	candidates.push((config.clone(), None));
+	candidates
+}
+
+// the values of each argument in a subject and the (type) template they were read with
+pub(crate) fn parse(
+	config: &GlitterRc,
+	subject: &str,
+) -> anyhow::Result<Option<(GlitterRc, Values)>> {
+	for (candidate, typed) in candidates(config) {
+		if let Some(values) = parse_subject(&candidate, subject)? {
+			let matches_type = typed.as_ref().is_none_or(|(idx, expected)| {
+				values
+					.iter()
+					.any(|(i, value)| i == idx && value == expected)
+			});
+			if matches_type {
+				return Ok(Some((candidate, values)));
+			}
+		}
+	}
+
+	Ok(None)
+}
+
+// everything wrong with a commit message, an empty list means it follows the template
+pub fn violations(config: &GlitterRc, message: &str) -> anyhow::Result<Vec<String>> {
+	let mut violations = Vec::new();
+	let mut lines = message.trim().splitn(2, '\n');
+	let subject = lines.next().unwrap_or_default().trim();
+	let paragraphs = lines
+		.next()
+		.unwrap_or_default()
+		.split("\n\n")
+		.map(|paragraph| paragraph.trim().to_owned())
+		.filter(|paragraph| !paragraph.is_empty())
+		.collect::<Vec<String>>();
+
+	match parse(config, subject)? {
+		None => violations.push(format!(
+			"`{}` doesn't match the commit message template {}",
+			subject,
+			config.commit_message.red()
+		)),
+		Some((candidate, values)) => {
+			let mut rest = Vec::new();
+			rest_arguments(&candidate, &tokenize(&candidate.commit_message)?, &mut rest);
+			let mut checked: Vec<(usize, String)> = Vec::new();
+			for (idx, value) in values {
+				// empty optional groups and arguments used twice are only checked once
+				if value.is_empty() {
+					continue;
+				}
+				if let Some((_, first)) = checked.iter().find(|(i, _)| *i == idx) {
+					if *first != value {
+						violations.push(format!(
+							"Argument {} is used more than once, but is `{}` in one place and `{}` in another",
+							idx, first, value
+						));
+					}
+					continue;
+				}
+				for arg in candidate
+					.commit_message_arguments
+					.iter()
+					.flatten()
+					.filter(|arg| arg.argument == idx as i32)
+				{
+					let mut expected = value.clone();
+					if let Some(case) = arg.word_case {
+						expected = expected
+							.split(' ')
+							.map(|word| convert_case(word, case))
+							.collect::<Vec<String>>()
+							.join(" ");
+					}
+					if let Some(case) = arg.case {
+						expected = convert_case(&expected, case);
+					}
+					if expected != value {
+						violations.push(format!(
+							"{} `{}` should be written as `{}`",
+							arg.label(),
+							value,
+							expected.green()
+						));
+					}
+					if let Err(err) = validate_argument(arg, &value, rest.contains(&idx)) {
+						violations.push(err.to_string());
+					}
+				}
+				checked.push((idx, value));
+			}
+		}
+	}
+
+	for key in missing_trailers(config, &paragraphs) {
+		violations.push(format!("The `{}` trailer is required", key));
+	}
+	if config.preset == Some(Preset::Conventional) {
+		if let Err(err) = conventional::validate(subject, &paragraphs) {
+			violations.push(err.to_string());
+		}
+	}
+	Ok(violations)
+}
+
+// `(label, message)` for every commit in the revisions, merges are skipped
+pub(crate) fn range_messages(revisions: &[&str]) -> anyhow::Result<Vec<(String, String)>> {
+	let output = Command::new("git")
+		.args(["log", "--no-merges", "--format=%h%x00%B%x1e"])
+		.args(revisions)
+		.output()?;
+	if !output.status.success() {
+		return Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"{} Couldn't read the commits in `{}`: {}",
+				"Fatal".red(),
+				revisions.join(" "),
+				String::from_utf8_lossy(&output.stderr).trim()
+			),
+		)));
+	}
+	Ok(String::from_utf8_lossy(&output.stdout)
+		.split('\u{1e}')
+		.filter_map(|entry| entry.trim_start().split_once('\0'))
+		.map(|(sha, message)| (sha.to_owned(), message.to_owned()))
+		.rev()
+		.collect())
+}
+
+// git leaves its instructions in the message as comments
+pub(crate) fn strip_comments(message: &str) -> String {
+	message
+		.lines()
+		.filter(|line| !line.starts_with('#'))
+		.collect::<Vec<&str>>()
+		.join("\n")
+}
+
+// print every message with its violations, failing if any of them has one
+pub(crate) fn report(config: &GlitterRc, messages: &[(String, String)]) -> anyhow::Result<()> {
+	let mut failed = 0;
+	for (label, message) in messages {
+		let subject = message.trim().lines().next().unwrap_or_default();
+		let violations = violations(config, message)?;
+		if violations.is_empty() {
+			println!("{} {} {}", "✔".green(), label.dimmed(), subject);
+		} else {
+			failed += 1;
+			println!("{} {} {}", "✖".red(), label.dimmed(), subject);
+			for violation in violations {
+				println!("    {}", violation);
+			}
+		}
+	}
+
+	if failed > 0 {
+		return Err(anyhow::Error::new(Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"{} {} of {} commit messages don't follow the template.",
+				"Fatal".red(),
+				failed,
+				messages.len()
+			),
+		)));
+	}
+	if messages.is_empty() {
+		println!("No commits to lint.");
+	}
+	Ok(())
+}
+
+pub fn lint(config: GlitterRc, args: Arguments) -> anyhow::Result<()> {
+	let config = select_template(config, args.template.as_deref())?;
+	let revisions = args
+		.arguments
+		.iter()
+		.map(String::as_str)
+		.collect::<Vec<&str>>();
+	let messages = match revisions.as_slice() {
+		["-"] | [] => {
+			if args.arguments.is_empty() && stdin().is_terminal() {
+				return Err(anyhow::Error::new(Error::new(
+					std::io::ErrorKind::InvalidInput,
+					"Pass a revision range (eg. `origin/main..HEAD`) or pipe a message into `glitter lint`.",
+				)));
+			}
+			let mut message = String::new();
+			stdin().read_to_string(&mut message)?;
+			vec![("stdin".to_owned(), strip_comments(&message))]
+		}
+		// every revision is handed to `git log`, eg. `main..HEAD ^release`
+		revisions => range_messages(revisions)?,
+	};
+	report(&config, &messages)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use crate::config::{Case, CommitMessageArguments, GlitterRc, Preset, Template};
+	use crate::conventional::apply_preset;
+
+	use super::violations;
+
+	#[test]
+	fn reverse_parses_the_template() {
+		let config = GlitterRc {
+			commit_message: "$1$[($2)]: $3+".to_owned(),
+			commit_message_arguments: Some(vec![
+				CommitMessageArguments {
+					argument: 1,
+					case: Some(Case::Lower),
+					type_enums: Some(vec!["fix".to_owned(), "feat".to_owned()]),
+					..Default::default()
+				},
+				CommitMessageArguments {
+					argument: 3,
+					no_trailing_period: Some(true),
+					..Default::default()
+				},
+			]),
+			..Default::default()
+		};
+
+		assert!(violations(&config, "feat(cli): add lint")
+			.unwrap()
+			.is_empty());
+		assert!(violations(&config, "fix: typo\n\nSome body.")
+			.unwrap()
+			.is_empty());
+		assert_eq!(violations(&config, "Update README.md").unwrap().len(), 1);
+
+		let found = violations(&config, "Feat: add lint.").unwrap();
+		assert_eq!(found.len(), 3, "{:?}", found);
+		assert!(found[0].contains("feat"));
+	}
+
+	#[test]
+	fn picks_type_templates() {
+		let mut type_templates = HashMap::new();
+		type_templates.insert(
+			"docs".to_owned(),
+			Template {
+				commit_message: "$1: $2+".to_owned(),
+				commit_message_arguments: None,
+				body: None,
+				footer: None,
+			},
+		);
+		let config = GlitterRc {
+			commit_message: "$1($2): $3+".to_owned(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 1,
+				type_enums: Some(vec!["fix".to_owned(), "docs".to_owned()]),
+				type_templates: Some(type_templates),
+				..Default::default()
+			}]),
+			..Default::default()
+		};
+
+		assert!(violations(&config, "docs: fix typo").unwrap().is_empty());
+		assert!(violations(&config, "fix(cli): typo").unwrap().is_empty());
+		assert_eq!(violations(&config, "fix: typo").unwrap().len(), 1);
+	}
+
+	#[test]
+	fn conventional_preset() {
+		let config = apply_preset(
+			GlitterRc {
+				preset: Some(Preset::Conventional),
+				..Default::default()
+			},
+			false,
+		);
+
+		assert!(violations(&config, "fix(cli): handle empty input")
+			.unwrap()
+			.is_empty());
+		assert!(
+			violations(&config, "feat!: drop v1\n\nBREAKING CHANGE: v1 is gone")
+				.unwrap()
+				.is_empty()
+		);
+		assert!(!violations(&config, "fixed a bug").unwrap().is_empty());
+	}
+}
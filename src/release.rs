@@ -0,0 +1,355 @@
+use crate::changelog::{entries, group, markdown, Entry};
+use crate::cli::{format_date, git_output, run_cmd, run_tasks, select_template};
+use crate::config::{Arguments, Bump, GlitterRc};
+use crate::lint::range_messages;
+use colored::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the version is available to release tasks through this variable
+pub const VERSION: &str = "GLITTER_RELEASE_VERSION";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Version {
+	pub major: u64,
+	pub minor: u64,
+	pub patch: u64,
+	/// pre-release identifier and number, eg. `("rc", 1)` for `-rc.1`
+	pub pre: Option<(String, u64)>,
+}
+
+impl Version {
+	// `v1.2.3` or `v1.2.3-rc.1`, with the tag prefix already known
+	pub fn parse(tag: &str, prefix: &str) -> Option<Version> {
+		let version = tag.strip_prefix(prefix)?;
+		let (core, pre) = match version.split_once('-') {
+			Some((core, pre)) => (core, Some(pre)),
+			None => (version, None),
+		};
+		let numbers = core
+			.split('.')
+			.map(|part| part.parse::<u64>().ok())
+			.collect::<Option<Vec<u64>>>()?;
+		if numbers.len() != 3 {
+			return None;
+		}
+		let pre = match pre {
+			Some(pre) => Some(match pre.split_once('.') {
+				Some((id, number)) => (id.to_owned(), number.parse::<u64>().ok()?),
+				None => (pre.to_owned(), 0),
+			}),
+			None => None,
+		};
+		Some(Version {
+			major: numbers[0],
+			minor: numbers[1],
+			patch: numbers[2],
+			pre,
+		})
+	}
+
+	fn core(&self) -> (u64, u64, u64) {
+		(self.major, self.minor, self.patch)
+	}
+
+	fn bump(&self, bump: Bump) -> Version {
+		let (major, minor, patch) = match bump {
+			Bump::Major => (self.major + 1, 0, 0),
+			Bump::Minor => (self.major, self.minor + 1, 0),
+			Bump::Patch => (self.major, self.minor, self.patch + 1),
+		};
+		Version {
+			major,
+			minor,
+			patch,
+			pre: None,
+		}
+	}
+}
+
+impl fmt::Display for Version {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+		if let Some((ref id, number)) = self.pre {
+			write!(f, "-{}.{}", id, number)?;
+		}
+		Ok(())
+	}
+}
+
+fn default_bump(kind: &str) -> Option<Bump> {
+	match kind {
+		"feat" => Some(Bump::Minor),
+		"fix" | "perf" => Some(Bump::Patch),
+		_ => None,
+	}
+}
+
+// the biggest bump any of the entries asks for, `None` when there's nothing worth releasing.
+// configured bumps take precedence over the defaults for feat, fix and perf
+pub fn bump_for(bumps: Option<&HashMap<String, Bump>>, entries: &[Entry]) -> Option<Bump> {
+	entries
+		.iter()
+		.filter_map(|entry| {
+			if entry.breaking {
+				return Some(Bump::Major);
+			}
+			bumps
+				.and_then(|bumps| bumps.get(&entry.kind).copied())
+				.or_else(|| default_bump(&entry.kind))
+		})
+		.max()
+}
+
+// the newest release that isn't a pre-release
+pub fn latest_stable(tags: &[Version]) -> Option<&Version> {
+	tags.iter()
+		.filter(|tag| tag.pre.is_none())
+		.max_by_key(|tag| tag.core())
+}
+
+// bump the latest release, numbering pre-releases of the same version one after another
+pub fn next_version(tags: &[Version], bump: Bump, pre: Option<&str>) -> Version {
+	let mut next = latest_stable(tags)
+		.cloned()
+		.unwrap_or(Version {
+			major: 0,
+			minor: 0,
+			patch: 0,
+			pre: None,
+		})
+		.bump(bump);
+	if let Some(id) = pre {
+		let number = tags
+			.iter()
+			.filter(|tag| tag.core() == next.core())
+			.filter_map(|tag| tag.pre.as_ref())
+			.filter(|(tag_id, _)| tag_id == id)
+			.map(|(_, number)| *number)
+			.max()
+			.unwrap_or(0);
+		next.pre = Some((id.to_owned(), number + 1));
+	}
+	next
+}
+
+// `git tag` arguments for an annotated tag with the notes as its message. the default cleanup
+// would strip the `#` headings as comments, so the notes are kept verbatim
+fn tag_args<'a>(tag: &'a str, notes: &'a str) -> Vec<&'a str> {
+	vec!["tag", "-a", "--cleanup=verbatim", tag, "-m", notes]
+}
+
+pub fn release(config: GlitterRc, args: Arguments, dry: bool, verbose: bool) -> anyhow::Result<()> {
+	let config = select_template(config, args.template.as_deref())?;
+	let options = config.release.clone();
+	let prefix = options
+		.as_ref()
+		.and_then(|options| options.tag_prefix.clone())
+		.unwrap_or_else(|| "v".to_owned());
+	let pre = args.arguments.first().map(String::as_str);
+	if let Some(id) = pre {
+		if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"`{}` can't be used as a pre-release identifier, use letters, digits and `-`, eg. {}",
+					id,
+					"rc".green()
+				),
+			)));
+		}
+	}
+
+	let tags = git_output(&["tag", "--list"])
+		.lines()
+		.filter_map(|tag| Version::parse(tag.trim(), &prefix))
+		.collect::<Vec<Version>>();
+	let since = latest_stable(&tags).map(|latest| format!("{}{}", prefix, latest));
+	let range = match since {
+		Some(ref since) => format!("{}..HEAD", since),
+		None => "HEAD".to_owned(),
+	};
+	let mut messages = range_messages(&[&range])?;
+	messages.reverse();
+	let entries = entries(&config, &messages)?;
+
+	let bump = match bump_for(
+		options.as_ref().and_then(|options| options.bumps.as_ref()),
+		&entries,
+	) {
+		Some(bump) => bump,
+		None => {
+			return Err(anyhow::Error::new(Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!(
+					"Nothing to release, none of the {} commits since {} change the version.",
+					messages.len(),
+					since.unwrap_or_else(|| "the first commit".to_owned())
+				),
+			)))
+		}
+	};
+	let next = next_version(&tags, bump, pre);
+	let tag = format!("{}{}", prefix, next);
+
+	println!(
+		"Releasing {} {}",
+		tag.green().bold(),
+		format!(
+			"({} bump, {} commit{} since {})",
+			format!("{:?}", bump).to_lowercase(),
+			messages.len(),
+			if messages.len() == 1 { "" } else { "s" },
+			since.as_deref().unwrap_or("the first commit")
+		)
+		.dimmed()
+	);
+	if dry {
+		println!("{}", "(dry-run)".yellow());
+	}
+	let secs = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.expect("Time went backwards")
+		.as_secs();
+	let notes = markdown(&group(
+		&config,
+		tag.clone(),
+		Some(format_date(secs)),
+		&entries,
+	));
+	if dry || verbose {
+		println!("\n{}", notes);
+	}
+
+	std::env::set_var(VERSION, next.to_string());
+	if let Some(tasks) = options.and_then(|options| options.tasks) {
+		run_tasks(&config, &tasks, dry, verbose);
+	}
+	run_cmd(
+		"git",
+		tag_args(&tag, &notes),
+		dry,
+		verbose,
+		Some(&*format!("git tag -a {}", tag.green().underline())),
+	);
+	if !dry {
+		println!("Push the tag with `git push origin {}`", tag);
+	}
+	Ok(())
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use crate::changelog::Entry;
+	use crate::config::Bump;
+
+	use super::{bump_for, next_version, tag_args, Version};
+
+	fn entry(kind: &str, breaking: bool) -> Entry {
+		Entry {
+			sha: "abc1234".to_owned(),
+			kind: kind.to_owned(),
+			scope: None,
+			description: "x".to_owned(),
+			breaking,
+		}
+	}
+
+	#[test]
+	fn parses_versions() {
+		let version = Version::parse("v1.2.3-rc.4", "v").unwrap();
+		assert_eq!(version.pre, Some(("rc".to_owned(), 4)));
+		assert_eq!(version.to_string(), "1.2.3-rc.4");
+		assert_eq!(Version::parse("1.2.3", "").unwrap().to_string(), "1.2.3");
+		assert!(Version::parse("release-1", "v").is_none());
+		assert!(Version::parse("v1.2", "v").is_none());
+	}
+
+	#[test]
+	fn computes_the_bump() {
+		assert_eq!(bump_for(None, &[entry("fix", false)]), Some(Bump::Patch));
+		assert_eq!(
+			bump_for(None, &[entry("fix", false), entry("feat", false)]),
+			Some(Bump::Minor)
+		);
+		assert_eq!(
+			bump_for(None, &[entry("chore", true), entry("feat", false)]),
+			Some(Bump::Major)
+		);
+		assert_eq!(bump_for(None, &[entry("docs", false)]), None);
+
+		let mut bumps = HashMap::new();
+		bumps.insert("docs".to_owned(), Bump::Patch);
+		assert_eq!(
+			bump_for(Some(&bumps), &[entry("docs", false)]),
+			Some(Bump::Patch)
+		);
+		assert_eq!(
+			bump_for(Some(&bumps), &[entry("docs", false), entry("feat", false)]),
+			Some(Bump::Minor)
+		);
+	}
+
+	#[test]
+	fn next_versions() {
+		let tags = ["1.2.0", "1.1.9", "1.3.0-rc.1", "1.3.0-rc.2", "1.3.0-beta.1"]
+			.iter()
+			.filter_map(|tag| Version::parse(tag, ""))
+			.collect::<Vec<Version>>();
+		assert_eq!(next_version(&tags, Bump::Minor, None).to_string(), "1.3.0");
+		assert_eq!(
+			next_version(&tags, Bump::Minor, Some("rc")).to_string(),
+			"1.3.0-rc.3"
+		);
+		assert_eq!(
+			next_version(&tags, Bump::Major, Some("rc")).to_string(),
+			"2.0.0-rc.1"
+		);
+		assert_eq!(next_version(&[], Bump::Patch, None).to_string(), "0.0.1");
+	}
+
+	#[test]
+	fn tags_keep_the_headings() {
+		use std::fs;
+		use std::process::Command;
+
+		let dir = std::env::temp_dir().join(format!("glitter-release-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let git = |args: &[&str]| {
+			let output = Command::new("git")
+				.arg("-C")
+				.arg(&dir)
+				.args([
+					"-c",
+					"user.name=glitter",
+					"-c",
+					"user.email=glitter@example.com",
+				])
+				.args(args)
+				.output()
+				.unwrap();
+			assert!(output.status.success(), "{:?}", output);
+			String::from_utf8_lossy(&output.stdout).into_owned()
+		};
+		git(&["init", "-q"]);
+		git(&[
+			"commit",
+			"-q",
+			"--allow-empty",
+			"--no-verify",
+			"-m",
+			"feat: x",
+		]);
+
+		let notes = "## [v0.1.0] - 2024-01-01\n\n### Added\n\n- x (abc1234)\n";
+		git(&tag_args("v0.1.0", notes));
+		assert!(git(&["cat-file", "-p", "v0.1.0"]).ends_with(&format!("\n\n{}", notes)));
+
+		fs::remove_dir_all(&dir).ok();
+	}
+}
@@ -0,0 +1,273 @@
+use std::io::Error;
+
+// how a placeholder refers to an argument, either by position (`$2`) or by name (`$scope`)
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Key {
+	Index(usize),
+	Name(String),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Token {
+	Literal(String),
+	// `rest` is set for `$N+` placeholders, which take argument N and everything after it
+	// `default` comes from `${N:-default}` and is used when the argument isn't provided
+	Argument {
+		key: Key,
+		rest: bool,
+		default: Option<String>,
+	},
+	// `[...]` renders its contents only when every placeholder inside has a value
+	Group(Vec<Token>),
+}
+
+fn malformed(message: String) -> anyhow::Error {
+	anyhow::Error::new(Error::new(std::io::ErrorKind::InvalidInput, message))
+}
+
+// read a `2` or `scope` key starting at `i`, returning it with the index just past it
+fn read_key(chars: &[char], mut i: usize, column: usize) -> anyhow::Result<(Key, usize)> {
+	match chars.get(i) {
+		Some(c) if c.is_ascii_digit() => {
+			let start = i;
+			while i < chars.len() && chars[i].is_ascii_digit() {
+				i += 1;
+			}
+			let index = chars[start..i]
+				.iter()
+				.collect::<String>()
+				.parse::<usize>()?;
+			if index == 0 {
+				return Err(malformed(format!(
+					"Invalid placeholder `$0` at column {}. Arguments start at $1.",
+					column
+				)));
+			}
+			Ok((Key::Index(index), i))
+		}
+		Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+			let start = i;
+			while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+				i += 1;
+			}
+			Ok((Key::Name(chars[start..i].iter().collect()), i))
+		}
+		Some(c) => Err(malformed(format!(
+			"Unexpected `{}` after `$` at column {}. Use `$$` for a literal dollar sign.",
+			c, column
+		))),
+		None => Err(malformed(format!(
+			"Template ends with a lone `$` at column {}. Use `$$` for a literal dollar sign.",
+			column
+		))),
+	}
+}
+
+// split a commit template into literals, placeholders and optional groups
+// columns in errors are 1-based and count characters, not bytes
+pub fn tokenize(template: &str) -> anyhow::Result<Vec<Token>> {
+	let chars = template.chars().collect::<Vec<char>>();
+	// the bottom of the stack is the template itself, every `$[` pushes a group
+	let mut stack: Vec<(usize, Vec<Token>)> = vec![(0, Vec::new())];
+	let mut literal = String::new();
+	let mut i = 0;
+
+	while i < chars.len() {
+		let column = i + 1;
+		match chars[i] {
+			// plain brackets are literals, `]` only closes a group that was opened with `$[`
+			']' if stack.len() > 1 => {
+				let (_, mut group) = stack.pop().unwrap();
+				if !literal.is_empty() {
+					group.push(Token::Literal(std::mem::take(&mut literal)));
+				}
+				stack.last_mut().unwrap().1.push(Token::Group(group));
+				i += 1;
+				continue;
+			}
+			'$' => {}
+			c => {
+				literal.push(c);
+				i += 1;
+				continue;
+			}
+		}
+		i += 1;
+
+		if chars.get(i) == Some(&'[') {
+			if !literal.is_empty() {
+				let tokens = &mut stack.last_mut().unwrap().1;
+				tokens.push(Token::Literal(std::mem::take(&mut literal)));
+			}
+			stack.push((column, Vec::new()));
+			i += 1;
+			continue;
+		}
+		let (key, rest, default) = match chars.get(i) {
+			// `$$` and `$]` are escaped literals, `$]` is only needed inside a group
+			Some(c @ ('$' | ']')) => {
+				literal.push(*c);
+				i += 1;
+				continue;
+			}
+			Some('{') => {
+				let (key, next) = read_key(&chars, i + 1, column)?;
+				i = next;
+				let rest = chars.get(i) == Some(&'+');
+				if rest {
+					i += 1;
+				}
+				let mut default = None;
+				if chars.get(i) == Some(&':') && chars.get(i + 1) == Some(&'-') {
+					let start = i + 2;
+					i = start;
+					while i < chars.len() && chars[i] != '}' {
+						i += 1;
+					}
+					default = Some(chars[start..i].iter().collect::<String>());
+				}
+				if chars.get(i) != Some(&'}') {
+					return Err(malformed(format!(
+						"Unterminated `${{` at column {}. Expected `${{N}}` or `${{N:-default}}`.",
+						column
+					)));
+				}
+				i += 1;
+				(key, rest, default)
+			}
+			_ => {
+				let (key, next) = read_key(&chars, i, column)?;
+				i = next;
+				let rest = chars.get(i) == Some(&'+');
+				if rest {
+					i += 1;
+				}
+				(key, rest, None)
+			}
+		};
+
+		let tokens = &mut stack.last_mut().unwrap().1;
+		if !literal.is_empty() {
+			tokens.push(Token::Literal(std::mem::take(&mut literal)));
+		}
+		tokens.push(Token::Argument { key, rest, default });
+	}
+	if stack.len() > 1 {
+		return Err(malformed(format!(
+			"Unclosed `$[` at column {}. Close the optional group with `]`.",
+			stack.last().unwrap().0
+		)));
+	}
+	let (_, mut tokens) = stack.pop().unwrap();
+	if !literal.is_empty() {
+		tokens.push(Token::Literal(literal));
+	}
+
+	Ok(tokens)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use super::{tokenize, Key, Token};
+
+	#[test]
+	fn multi_digit_indices() {
+		assert_eq!(
+			tokenize("$1: $10 $12+").unwrap(),
+			vec![
+				Token::Argument {
+					key: Key::Index(1),
+					rest: false,
+					default: None
+				},
+				Token::Literal(": ".to_owned()),
+				Token::Argument {
+					key: Key::Index(10),
+					rest: false,
+					default: None
+				},
+				Token::Literal(" ".to_owned()),
+				Token::Argument {
+					key: Key::Index(12),
+					rest: true,
+					default: None
+				},
+			]
+		)
+	}
+
+	#[test]
+	fn escaped_dollars_and_names() {
+		assert_eq!(
+			tokenize("$$5 $type+").unwrap(),
+			vec![
+				Token::Literal("$5 ".to_owned()),
+				Token::Argument {
+					key: Key::Name("type".to_owned()),
+					rest: true,
+					default: None
+				},
+			]
+		)
+	}
+
+	#[test]
+	fn malformed_tokens() {
+		let err = tokenize("$1: $(x)").unwrap_err().to_string();
+		assert!(err.contains("column 5"), "{}", err);
+
+		assert!(tokenize("$1 $-").is_err());
+		assert!(tokenize("costs 5$").is_err());
+		assert!(tokenize("$0").is_err());
+		assert!(tokenize("${2:-core").is_err());
+		assert!(tokenize("$1$[($2)").is_err());
+	}
+
+	#[test]
+	fn defaults_and_groups() {
+		assert_eq!(
+			tokenize("$1$[($2)]: ${3+:-wip} $[x$]]").unwrap(),
+			vec![
+				Token::Argument {
+					key: Key::Index(1),
+					rest: false,
+					default: None
+				},
+				Token::Group(vec![
+					Token::Literal("(".to_owned()),
+					Token::Argument {
+						key: Key::Index(2),
+						rest: false,
+						default: None
+					},
+					Token::Literal(")".to_owned()),
+				]),
+				Token::Literal(": ".to_owned()),
+				Token::Argument {
+					key: Key::Index(3),
+					rest: true,
+					default: Some("wip".to_owned())
+				},
+				Token::Literal(" ".to_owned()),
+				Token::Group(vec![Token::Literal("x]".to_owned())]),
+			]
+		)
+	}
+
+	#[test]
+	fn plain_brackets_are_literals() {
+		assert_eq!(
+			tokenize("[WIP] $1+ ]").unwrap(),
+			vec![
+				Token::Literal("[WIP] ".to_owned()),
+				Token::Argument {
+					key: Key::Index(1),
+					rest: true,
+					default: None
+				},
+				Token::Literal(" ]".to_owned()),
+			]
+		)
+	}
+}
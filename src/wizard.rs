@@ -0,0 +1,291 @@
+use crate::cli::{argument_index, format_argument, select_type_template};
+use crate::config::{Arguments, GlitterRc};
+use crate::template::{tokenize, Key, Token};
+use colored::*;
+use std::collections::HashMap;
+use std::io::{BufRead, Error, Write};
+
+// an argument position used by the template
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Placeholder {
+	pub index: usize,
+	pub rest: bool,
+	// only used inside `[...]` groups or with a `${N:-default}`
+	pub optional: bool,
+	pub default: Option<String>,
+}
+
+fn collect(config: &GlitterRc, tokens: &[Token], in_group: bool, found: &mut Vec<Placeholder>) {
+	for token in tokens {
+		match token {
+			Token::Literal(_) => {}
+			Token::Group(group) => collect(config, group, true, found),
+			Token::Argument { key, rest, default } => {
+				// names that aren't bound to a position are variables, there's nothing to ask for
+				if let Some(index) = argument_index(config, key) {
+					let optional = in_group || default.is_some();
+					match found.iter_mut().find(|p| p.index == index) {
+						Some(existing) => {
+							existing.optional &= optional;
+							existing.rest |= *rest;
+						}
+						None => found.push(Placeholder {
+							index,
+							rest: *rest,
+							optional,
+							default: default.clone(),
+						}),
+					}
+				}
+			}
+		}
+	}
+}
+
+// every argument position used by the commit message, body and footer, in order
+pub fn placeholders(config: &GlitterRc) -> anyhow::Result<Vec<Placeholder>> {
+	let mut found: Vec<Placeholder> = Vec::new();
+	let templates = [
+		Some(&config.commit_message),
+		config.body.as_ref(),
+		config.footer.as_ref(),
+	];
+	for template in templates.into_iter().flatten() {
+		collect(config, &tokenize(template)?, false, &mut found);
+	}
+	found.sort_by_key(|p| p.index);
+	Ok(found)
+}
+
+// whether a required argument is missing, ie. the message can't be rendered as is
+pub fn is_missing_arguments(config: &GlitterRc, args: &Arguments) -> anyhow::Result<bool> {
+	Ok(placeholders(config)?.iter().any(|p| {
+		!p.optional
+			&& args
+				.arguments
+				.get(p.index - 1)
+				.is_none_or(|value| value.is_empty())
+	}))
+}
+
+fn label(config: &GlitterRc, index: usize) -> String {
+	config
+		.commit_message_arguments
+		.iter()
+		.flatten()
+		.find(|arg| arg.argument == index as i32)
+		.and_then(|arg| arg.name.clone())
+		.unwrap_or_else(|| format!("${}", index))
+}
+
+// the message as far as it's filled in, with `<name>` where answers are still missing
+fn preview(
+	config: &GlitterRc,
+	args: &Arguments,
+	variables: &HashMap<String, String>,
+	tokens: &[Token],
+) -> String {
+	let mut result = String::new();
+	for token in tokens {
+		match token {
+			Token::Literal(text) => result.push_str(text),
+			Token::Group(group) => result.push_str(&preview(config, args, variables, group)),
+			Token::Argument { key, rest, default } => {
+				let value = match (argument_index(config, key), key) {
+					(Some(index), _) if *rest => Some(
+						args.arguments
+							.get(index - 1..)
+							.unwrap_or_default()
+							.join(" "),
+					),
+					(Some(index), _) => args.arguments.get(index - 1).cloned(),
+					(None, Key::Name(name)) => variables.get(name).cloned(),
+					(None, Key::Index(_)) => None,
+				}
+				.filter(|value| !value.trim().is_empty())
+				.or_else(|| default.clone());
+
+				match value {
+					Some(value) => result.push_str(&value),
+					None => {
+						let name = match (argument_index(config, key), key) {
+							(Some(index), _) => label(config, index),
+							(None, Key::Name(name)) => name.clone(),
+							(None, Key::Index(index)) => format!("${}", index),
+						};
+						result.push_str(&format!("<{}>", name).dimmed().to_string());
+					}
+				}
+			}
+		}
+	}
+	result
+}
+
+// ask for every argument that hasn't been passed on the command line, validating each answer
+// before moving on. type enums can be picked by their number in the list
+pub fn prompt(
+	config: &GlitterRc,
+	mut args: Arguments,
+	variables: &HashMap<String, String>,
+	input: &mut impl BufRead,
+) -> anyhow::Result<Arguments> {
+	let mut asked: Vec<usize> = Vec::new();
+
+	loop {
+		// answering the type argument can switch to one of its type_templates
+		let current =
+			select_type_template(config.clone(), &args).unwrap_or_else(|_| config.clone());
+		let next = placeholders(&current)?.into_iter().find(|p| {
+			!asked.contains(&p.index)
+				&& args
+					.arguments
+					.get(p.index - 1)
+					.is_none_or(|value| value.is_empty())
+		});
+		let placeholder = match next {
+			Some(placeholder) => placeholder,
+			None => break,
+		};
+		asked.push(placeholder.index);
+
+		println!(
+			"\n{} {}",
+			"Commit message:".bold(),
+			preview(
+				&current,
+				&args,
+				variables,
+				&tokenize(&current.commit_message)?
+			)
+		);
+		let type_enums = current
+			.commit_message_arguments
+			.iter()
+			.flatten()
+			.find(|arg| arg.argument == placeholder.index as i32)
+			.and_then(|arg| arg.type_enums.clone())
+			.unwrap_or_default();
+		for (i, value) in type_enums.iter().enumerate() {
+			println!("  {} {}", format!("{})", i + 1).dimmed(), value);
+		}
+
+		let value = loop {
+			print!(
+				"{}{}{}: ",
+				label(&current, placeholder.index).green(),
+				if placeholder.rest { " (rest)" } else { "" },
+				match (&placeholder.default, placeholder.optional) {
+					(Some(default), _) => format!(" [{}]", default),
+					(None, true) => " (optional)".to_owned(),
+					(None, false) => String::new(),
+				}
+			);
+			std::io::stdout().flush()?;
+
+			let mut line = String::new();
+			if input.read_line(&mut line)? == 0 {
+				return Err(anyhow::Error::new(Error::new(
+					std::io::ErrorKind::UnexpectedEof,
+					"Aborted, no input left to answer with.",
+				)));
+			}
+			let mut value = line.trim().to_owned();
+
+			if value.is_empty() {
+				if placeholder.optional {
+					break value;
+				}
+				println!("{} This argument is required.", "Error".red());
+				continue;
+			}
+			if let Ok(number) = value.parse::<usize>() {
+				if number >= 1 && number <= type_enums.len() {
+					value = type_enums[number - 1].clone();
+				}
+			}
+			match format_argument(&current, placeholder.index, &value, placeholder.rest) {
+				Ok(_) => break value,
+				Err(err) => println!("{} {}", "Error".red(), err),
+			}
+		};
+
+		if args.arguments.len() < placeholder.index {
+			args.arguments.resize(placeholder.index, String::new());
+		}
+		args.arguments[placeholder.index - 1] = value;
+	}
+	Ok(args)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use std::io::Cursor;
+	use std::path::PathBuf;
+
+	use crate::config::{Arguments, CommitMessageArguments, GlitterRc};
+
+	use super::{is_missing_arguments, placeholders, prompt};
+
+	fn config() -> GlitterRc {
+		GlitterRc {
+			commit_message: "$type$[($2)]: $3+".to_string(),
+			commit_message_arguments: Some(vec![CommitMessageArguments {
+				argument: 1,
+				name: Some("type".to_owned()),
+				type_enums: Some(vec!["fix".to_owned(), "feat".to_owned()]),
+				..Default::default()
+			}]),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn finds_placeholders() {
+		let found = placeholders(&config()).unwrap();
+		assert_eq!(
+			found
+				.iter()
+				.map(|p| (p.index, p.optional))
+				.collect::<Vec<_>>(),
+			vec![(1, false), (2, true), (3, false)]
+		);
+		assert!(found[2].rest);
+	}
+
+	#[test]
+	fn prompts_for_missing_arguments() {
+		let args = Arguments {
+			action: "commit".to_string(),
+			arguments: vec![],
+			rc_path: PathBuf::new(),
+			dry: Some(Some(false)),
+			raw: Some(Some(false)),
+			no_verify: Some(Some(false)),
+			verbose: Some(Some(false)),
+			no_add: Some(Some(false)),
+			interactive: Some(Some(true)),
+			body: vec![],
+			template: None,
+			breaking: Some(Some(false)),
+			format: None,
+			with: vec![],
+			trailer: vec![],
+		};
+		assert!(is_missing_arguments(&config(), &args).unwrap());
+
+		// `chore` isn't a type enum so it's asked again, `2` picks `feat` from the list
+		let mut input = Cursor::new("chore\n2\n\nadd the wizard\n");
+		let args = prompt(&config(), args, &HashMap::new(), &mut input).unwrap();
+		assert_eq!(args.arguments, vec!["feat", "", "add the wizard"]);
+		assert!(!is_missing_arguments(&config(), &args).unwrap());
+
+		let args = Arguments {
+			arguments: vec![],
+			..args
+		};
+		assert!(prompt(&config(), args, &HashMap::new(), &mut Cursor::new("fix\n")).is_err());
+	}
+}